@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS bot_data
+(
+    data_key   VARCHAR(64) PRIMARY KEY,
+    data_value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS records
+(
+    id         VARCHAR(36) PRIMARY KEY,
+    title      TEXT        NOT NULL,
+    remark     TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS content
+(
+    uuid          VARCHAR(36) NOT NULL,
+    content       TEXT        NOT NULL,
+    content_type  VARCHAR(16) NOT NULL,
+    delete_status BOOLEAN     NOT NULL DEFAULT FALSE,
+    create_time   TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS content_uuid_index ON content (uuid);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS user_roles
+(
+    user_id  BIGINT      NOT NULL,
+    group_id BIGINT      NOT NULL DEFAULT 0,
+    role     VARCHAR(16) NOT NULL,
+    PRIMARY KEY (user_id, group_id)
+);
@@ -0,0 +1 @@
+ALTER TABLE records ADD COLUMN group_id BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE records ADD COLUMN revision BIGINT NOT NULL DEFAULT 0;
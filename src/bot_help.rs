@@ -1,124 +1,74 @@
-use crate::config::DataBaseConfig;
-use crate::status::BotStatus;
-use anyhow::Result;
+use crate::config::{DataBaseConfig, InitBotDataConfig};
+use crate::role::Role;
+use crate::session::SessionStore;
+use crate::storage::{self, Content, Record, Storage};
+use anyhow::{bail, Result};
 use chrono::{DateTime, Local};
 use onebot_v11::connect::ws::WsConnect;
-use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
 use std::sync::Arc;
+use tokio::sync::Mutex;
 use tracing::info;
 
 pub struct BotHelp {
-    pool: PgPool,
+    storage: Box<dyn Storage>,
     pub ws_connect: Arc<WsConnect>,
-}
-
-#[derive(sqlx::FromRow)]
-pub struct Record {
-    pub id: String,
-    pub title: String,
-    pub remark: Option<String>,
-    pub created_at: DateTime<Local>,
-}
-
-#[derive(sqlx::FromRow)]
-pub struct Content {
-    pub uuid: String,
-    pub content: String,
-    pub content_type: String,
+    pub sessions: SessionStore,
+    /// 生成与推送都会修改文档仓库，定时发布与手动指令不能同时进行
+    pub publish_lock: Mutex<()>,
 }
 
 impl BotHelp {
     pub async fn init(config: &DataBaseConfig, ws_connect: Arc<WsConnect>) -> Result<Self> {
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .connect(&format!(
-                "postgres://{}:{}@{}:{}/{}",
-                config.username, config.password, config.host, config.port, config.database
-            ))
-            .await?;
-        Ok(BotHelp { pool, ws_connect })
+        let storage = storage::connect(config).await?;
+        storage.migrate().await?;
+        let bot_help = BotHelp {
+            storage,
+            ws_connect,
+            sessions: SessionStore::default(),
+            publish_lock: Mutex::new(()),
+        };
+        bot_help.init_bot_data(&config.init_data).await?;
+        info!("Database Init Success");
+        Ok(bot_help)
+    }
+
+    async fn init_bot_data(&self, config: &InitBotDataConfig) -> Result<()> {
+        // 没有所有者时无法接收错误提醒，也无人能授予权限
+        if config.admin == 0 {
+            bail!("未配置机器人所有者，请在配置文件中设置 data_base.init_data.admin");
+        }
+        let init_data = [
+            ("share_path", config.share_path.clone()),
+            ("max_title_length", config.max_title_length.to_string()),
+        ];
+        for (key, value) in init_data {
+            self.storage.insert_bot_data(key, value).await?;
+        }
+        // 所有者始终以配置为准
+        self.storage
+            .upsert_bot_data("admin", config.admin.to_string())
+            .await
     }
 
     pub async fn bot_admin(&self) -> Result<i64> {
-        let row: (String,) =
-            sqlx::query_as("SELECT data_value FROM bot_data WHERE data_key = 'admin'")
-                .fetch_one(&self.pool)
-                .await?;
-        Ok(row.0.parse::<i64>()?)
-    }
-
-    pub async fn bot_status(&self) -> Result<BotStatus> {
-        let row: (String,) =
-            sqlx::query_as("SELECT data_value FROM bot_data WHERE data_key = 'status'")
-                .fetch_one(&self.pool)
-                .await?;
-        Ok(BotStatus::from(row.0))
+        Ok(self.storage.select_bot_data("admin").await?.parse::<i64>()?)
     }
 
     pub async fn max_title_length(&self) -> Result<usize> {
-        let row: (String,) =
-            sqlx::query_as("SELECT data_value FROM bot_data WHERE data_key = 'max_title_length'")
-                .fetch_one(&self.pool)
-                .await?;
-        Ok(row.0.parse::<usize>()?)
-    }
-
-    pub async fn update_status(&self, status: BotStatus) -> Result<()> {
-        sqlx::query("UPDATE bot_data SET data_value = $1 WHERE data_key = 'status'")
-            .bind(status.to_string())
-            .execute(&self.pool)
-            .await?;
-        Ok(())
-    }
-
-    pub async fn check_record_user_id(&self, user_id: i64) -> Result<bool> {
-        let row: (String,) =
-            sqlx::query_as("SELECT data_value FROM bot_data WHERE data_key = 'record_user_id'")
-                .fetch_one(&self.pool)
-                .await?;
-        Ok(row.0.parse::<i64>()? == user_id)
-    }
-
-    pub async fn set_record_user_id(&self, user_id: i64) -> Result<()> {
-        sqlx::query("UPDATE bot_data SET data_value = $1 WHERE data_key = 'record_user_id'")
-            .bind(user_id)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+        Ok(self
+            .storage
+            .select_bot_data("max_title_length")
+            .await?
+            .parse::<usize>()?)
     }
 
     pub async fn share_path(&self) -> Result<String> {
-        let row: (String,) =
-            sqlx::query_as("SELECT data_value FROM bot_data WHERE data_key = 'share_path'")
-                .fetch_one(&self.pool)
-                .await?;
-        Ok(row.0)
+        self.storage.select_bot_data("share_path").await
     }
 
-    pub async fn recording_uuid(&self) -> Result<String> {
-        let row: (String,) =
-            sqlx::query_as("SELECT data_value FROM bot_data WHERE data_key = 'recording_uuid'")
-                .fetch_one(&self.pool)
-                .await?;
-        Ok(row.0)
-    }
-
-    pub async fn set_recording_uuid(&self, uuid: String) -> Result<()> {
-        sqlx::query("UPDATE bot_data SET data_value = $1 WHERE data_key = 'recording_uuid'")
-            .bind(uuid)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
-    }
-
-    pub async fn insert_new_record(&self, title: String) -> Result<String> {
+    pub async fn insert_new_record(&self, group_id: i64, title: String) -> Result<String> {
         let uuid = uuid::Uuid::new_v4().to_string();
-        sqlx::query("INSERT INTO records (id, title) VALUES ($1, $2)")
-            .bind(&uuid)
-            .bind(title)
-            .execute(&self.pool)
-            .await?;
+        self.storage.insert_record(&uuid, group_id, title).await?;
         Ok(uuid)
     }
 
@@ -129,31 +79,17 @@ impl BotHelp {
         content_type: String,
     ) -> Result<()> {
         info!("Record Content To {}: {}", uuid, content);
-        sqlx::query("INSERT INTO content (uuid, content, content_type) VALUES ($1, $2, $3)")
-            .bind(uuid)
-            .bind(content)
-            .bind(content_type)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+        self.storage
+            .insert_content(&uuid, content, content_type)
+            .await
     }
 
     pub async fn set_record_remark(&self, remark: String, uuid: String) -> Result<()> {
-        sqlx::query("UPDATE records SET remark = $1 WHERE id = $2")
-            .bind(remark)
-            .bind(uuid)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+        self.storage.update_record_remark(&uuid, remark).await
     }
 
     pub async fn select_all_records(&self) -> Result<Vec<Record>> {
-        let rows: Vec<Record> = sqlx::query_as(
-            "SELECT id, title, remark, created_at FROM records ORDER BY created_at ASC",
-        )
-        .fetch_all(&self.pool)
-        .await?;
-        Ok(rows)
+        self.storage.select_all_records().await
     }
 
     pub async fn select_records_by_date(
@@ -161,47 +97,109 @@ impl BotHelp {
         start_date: DateTime<Local>,
         end_date: DateTime<Local>,
     ) -> Result<Vec<Record>> {
-        let rows: Vec<Record> = sqlx::query_as(
-            "SELECT id, title, remark, created_at FROM records WHERE created_at >= $1 and created_at <= $2 ORDER BY created_at ASC",
-        )
-            .bind(start_date)
-            .bind(end_date)
-            .fetch_all(&self.pool)
-            .await?;
-        Ok(rows)
-    }
-
-    pub async fn select_all_content_by_uuid(&self, uuid: &String) -> Result<Vec<Content>> {
-        let rows: Vec<Content> = sqlx::query_as(
-            "SELECT uuid, content, content_type FROM content WHERE uuid = $1 and delete_status = false ORDER BY create_time ASC",
-        )
-            .bind(uuid)
-            .fetch_all(&self.pool)
-            .await?;
-        Ok(rows)
-    }
-
-    pub async fn delete_record(&self, uuid: &String) -> Result<()> {
-        sqlx::query("DELETE FROM records WHERE id = $1")
-            .bind(uuid)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
-    }
-
-    pub async fn delete_content(&self, uuid: &String) -> Result<()> {
-        sqlx::query("DELETE FROM content WHERE uuid = $1")
-            .bind(uuid)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
-    }
-    
-    pub async fn set_tmp_content(&self, content: String) -> Result<()> {
-        sqlx::query("UPDATE bot_data SET data_value = $1 WHERE data_key = 'tmp_content'")
-            .bind(content)
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+        self.storage
+            .select_records_by_date(start_date, end_date)
+            .await
+    }
+
+    pub async fn select_all_content(&self) -> Result<Vec<Content>> {
+        self.storage.select_all_content().await
+    }
+
+    pub async fn select_content(&self, uuid: &str) -> Result<Vec<Content>> {
+        self.storage.select_content(uuid).await
+    }
+
+    pub async fn select_record(&self, uuid: &str) -> Result<Option<Record>> {
+        self.storage.select_record(uuid).await
+    }
+
+    pub async fn select_last_record(&self, group_id: i64) -> Result<Option<Record>> {
+        self.storage.select_last_record(group_id).await
+    }
+
+    pub async fn update_content(
+        &self,
+        uuid: &str,
+        old_content: &str,
+        new_content: String,
+    ) -> Result<()> {
+        self.storage
+            .update_content(uuid, old_content, new_content)
+            .await
+    }
+
+    pub async fn delete_record(&self, uuid: &str) -> Result<()> {
+        self.storage.delete_record(uuid).await
+    }
+
+    pub async fn delete_content(&self, uuid: &str) -> Result<()> {
+        self.storage.delete_content(uuid).await
+    }
+
+    /// 用户已分配的身份，`None` 表示全局身份
+    pub async fn user_roles(&self, user_id: i64) -> Result<Vec<(Option<i64>, Role)>> {
+        self.storage
+            .select_user_roles(user_id)
+            .await?
+            .iter()
+            .map(|role| Ok((group_scope(role.group_id), role.role.parse()?)))
+            .collect()
+    }
+
+    /// 所有已分配的身份：(用户, 群号, 身份)
+    pub async fn all_user_roles(&self) -> Result<Vec<(i64, Option<i64>, Role)>> {
+        self.storage
+            .select_all_user_roles()
+            .await?
+            .iter()
+            .map(|role| Ok((role.user_id, group_scope(role.group_id), role.role.parse()?)))
+            .collect()
+    }
+
+    pub async fn set_user_role(
+        &self,
+        user_id: i64,
+        group_id: Option<i64>,
+        role: Role,
+    ) -> Result<()> {
+        self.storage
+            .upsert_user_role(
+                user_id,
+                group_id.unwrap_or_default(),
+                role.as_str().to_string(),
+            )
+            .await
+    }
+
+    pub async fn remove_user_role(&self, user_id: i64, group_id: Option<i64>) -> Result<bool> {
+        self.storage
+            .delete_user_role(user_id, group_id.unwrap_or_default())
+            .await
+    }
+}
+
+/// 数据库中以 0 表示全局
+fn group_scope(group_id: i64) -> Option<i64> {
+    Some(group_id).filter(|group_id| *group_id != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CoreConfig, DataBaseKind};
+    use crate::test_utils::MockOneBot;
+    use onebot_v11::connect::ws::WsConnect;
+
+    #[tokio::test]
+    async fn refuses_to_start_without_admin() {
+        let onebot = MockOneBot::start().await;
+        let mut config = CoreConfig {
+            bot_ws: onebot.ws_config(),
+            ..CoreConfig::default()
+        };
+        config.data_base.kind = DataBaseKind::Memory;
+        let ws_connect = WsConnect::new(config.bot_ws.clone()).await.unwrap();
+        assert!(BotHelp::init(&config.data_base, ws_connect).await.is_err());
     }
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use crate::log::LogConfig;
-use anyhow::Result;
+use crate::role::Role;
+use anyhow::{anyhow, Result};
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
@@ -19,24 +21,247 @@ pub struct CoreConfig {
     pub data_base: DataBaseConfig,
     pub bot_ws: WsConfig,
     pub git: GitConfig,
+    pub record_session: RecordSessionConfig,
+    pub generate: GenerateConfig,
+    pub schedule: ScheduleConfig,
+    pub attachment: AttachmentConfig,
+    pub command: CommandConfig,
+    pub role: RoleConfig,
+    pub access: AccessConfig,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub enum DataBaseKind {
+    #[default]
+    Postgres,
+    /// SQLite 数据库文件路径
+    Sqlite(String),
+    /// 仅保存在内存中，重启后数据丢失
+    Memory,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct DataBaseConfig{
+    pub kind: DataBaseKind,
     pub username: String,
     pub password: String,
     pub host: String,
     pub port: u64,
     pub database: String,
+    /// 首次启动时写入 bot_data 的初始值，已存在的键不会被覆盖
+    pub init_data: InitBotDataConfig,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+pub struct InitBotDataConfig {
+    pub admin: i64,
+    pub share_path: String,
+    pub max_title_length: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RecordSessionConfig {
+    pub enable: bool,
+    /// 会话无操作多少秒后视为超时
+    pub idle_timeout: u64,
+    /// 检查超时会话的间隔（秒）
+    pub check_interval: u64,
+    pub timeout_action: TimeoutAction,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeoutAction {
+    /// 保留已记录的部分
+    Finalize,
+    /// 删除本次记录
+    #[default]
+    Rollback,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct GenerateConfig {
+    pub target: OutputTargetKind,
+    /// 自定义模板目录（root.md.j2、month.md.j2、day.md.j2、record.md.j2），缺少的模板使用默认模板
+    pub template_dir: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub enum OutputTargetKind {
+    #[default]
+    VuePressHope,
+    MkDocs {
+        /// 分享目录相对于 mkdocs docs_dir 的路径，例如 `share`
+        nav_prefix: String,
+    },
+    Hugo,
+    Docusaurus,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct CommandConfig {
+    /// 指令前缀，如 `/` 或 `#`，设置后群聊中无需 @机器人，`/ls` 即可触发指令
+    pub prefix: Option<String>,
+}
+
+/// 未分配身份的用户的默认身份，以及 QQ 群主、群管理员在本群中的身份
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RoleConfig {
+    pub default_role: Role,
+    /// 为空时不按群主映射
+    pub group_owner: Option<Role>,
+    /// 为空时不按群管理员映射
+    pub group_admin: Option<Role>,
+}
+
+/// 允许记录的群和用户，列表为空时不限制，所有者不受限制
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct AccessConfig {
+    /// 允许记录的群，其他群仍可使用查看等指令
+    pub groups: Vec<i64>,
+    /// 某些群中只允许这些用户记录
+    pub group_users: Vec<GroupUsersConfig>,
+    /// 在所有群聊和私聊中都忽略的用户
+    pub denied_users: Vec<i64>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct GroupUsersConfig {
+    pub group_id: i64,
+    pub users: Vec<i64>,
+}
+
+impl AccessConfig {
+    /// 不允许在该群记录时返回回复的原因
+    pub fn recording_refusal(&self, group_id: i64, user_id: i64) -> Option<&'static str> {
+        if !self.groups.is_empty() && !self.groups.contains(&group_id) {
+            return Some("本群未开启记录");
+        }
+        let allowed = self
+            .group_users
+            .iter()
+            .filter(|group| group.group_id == group_id)
+            .all(|group| group.users.contains(&user_id));
+        (!allowed).then_some("你没有在本群记录的权限")
+    }
+}
+
+/// 群文件、视频等附件的下载限制
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AttachmentConfig {
+    /// 单个附件的大小上限（字节）
+    pub max_size: u64,
+    /// 允许记录的扩展名，不区分大小写，为空时不限制
+    pub allowed_extensions: Vec<String>,
+}
+
+/// 定时生成并推送
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ScheduleConfig {
+    pub enable: bool,
+    /// cron 表达式：秒 分 时 日 月 星期 [年]
+    pub cron: String,
+    /// 生成的文件没有变化（包括新增和撤销记录）时跳过本次推送
+    pub skip_without_new_records: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GitConfig {
     pub repository_dir: String,
+    /// 远程仓库地址，如 `https://example.com/example/example.git` 或 `git@example.com:example/example.git`
     pub url: String,
+    /// 提交者名称
     pub username: String,
-    pub password: String,
+    /// 提交者邮箱
     pub user_email: String,
+    /// 旧版的 `password` 会转换为 `credential` 中的 `Token`
+    pub credential: GitCredential,
+    pub publish: PublishMode,
+    /// 默认在启动时把 `repository_dir` 加入全局 git 配置的 `safe.directory`，容器中挂载的仓库
+    /// 属主与运行用户不同也能打开；开启后改为关闭 libgit2 对仓库目录属主的检查，对进程打开的
+    /// 所有仓库生效，仅在无法写入全局 git 配置时使用
+    pub disable_owner_validation: bool,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub enum PublishMode {
+    /// 直接推送到当前分支
+    #[default]
+    Push,
+    /// 推送到带日期的分支，并向当前分支发起合并请求，适用于受保护的主分支
+    PullRequest(ForgeConfig),
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub enum ForgeKind {
+    #[default]
+    GitHub,
+    Gitea,
+    GitLab,
+}
+
+/// 代码托管平台 API，`Debug` 输出中不包含令牌
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ForgeConfig {
+    pub kind: ForgeKind,
+    /// 如 `https://api.github.com`、`https://gitea.example.com/api/v1`、`https://gitlab.example.com/api/v4`
+    pub api_url: String,
+    /// GitHub/Gitea 为 `owner/repo`，GitLab 为项目 ID 或 `group/project`
+    pub repository: String,
+    pub token: String,
+    /// 推送分支名前缀，后接日期时间
+    pub branch_prefix: String,
+}
+
+impl fmt::Debug for ForgeConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ForgeConfig")
+            .field("kind", &self.kind)
+            .field("api_url", &self.api_url)
+            .field("repository", &self.repository)
+            .field("token", &"***")
+            .field("branch_prefix", &self.branch_prefix)
+            .finish()
+    }
+}
+
+/// 推送凭据，`Debug` 输出中不包含密钥
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub enum GitCredential {
+    /// 不提供凭据，适用于本地路径或已由 ssh-agent 等方式处理认证的仓库
+    #[default]
+    None,
+    /// HTTPS 用户名 + 访问令牌
+    Token { username: String, token: String },
+    /// SSH 私钥
+    SshKey {
+        username: String,
+        private_key: String,
+        passphrase: Option<String>,
+    },
+}
+
+impl fmt::Debug for GitCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitCredential::None => write!(f, "None"),
+            GitCredential::Token { username, .. } => f
+                .debug_struct("Token")
+                .field("username", username)
+                .field("token", &"***")
+                .finish(),
+            GitCredential::SshKey {
+                username,
+                private_key,
+                passphrase,
+            } => f
+                .debug_struct("SshKey")
+                .field("username", username)
+                .field("private_key", private_key)
+                .field("passphrase", &passphrase.as_ref().map(|_| "***"))
+                .finish(),
+        }
+    }
 }
 
 impl Default for CoreConfig {
@@ -46,6 +271,13 @@ impl Default for CoreConfig {
             data_base: DataBaseConfig::default(),
             bot_ws: WsConfig::default(),
             git: GitConfig::default(),
+            record_session: RecordSessionConfig::default(),
+            generate: GenerateConfig::default(),
+            schedule: ScheduleConfig::default(),
+            attachment: AttachmentConfig::default(),
+            command: CommandConfig::default(),
+            role: RoleConfig::default(),
+            access: AccessConfig::default(),
         }
     }
 }
@@ -54,10 +286,70 @@ impl Default for GitConfig {
     fn default() -> Self {
         GitConfig {
             repository_dir: ".".to_string(),
-            url: "https://example.com:443/example/example".to_string(),
+            url: "https://example.com/example/example.git".to_string(),
             username: "example".to_string(),
-            password: "example".to_string(),
             user_email: "example@mail.example.com".to_string(),
+            credential: GitCredential::default(),
+            publish: PublishMode::default(),
+            disable_owner_validation: false,
+        }
+    }
+}
+
+impl Default for ForgeConfig {
+    fn default() -> Self {
+        ForgeConfig {
+            kind: ForgeKind::default(),
+            api_url: "https://api.github.com".to_string(),
+            repository: "example/example".to_string(),
+            token: String::new(),
+            branch_prefix: "bot/".to_string(),
+        }
+    }
+}
+
+impl Default for RoleConfig {
+    fn default() -> Self {
+        RoleConfig {
+            default_role: Role::Recorder,
+            group_owner: None,
+            group_admin: None,
+        }
+    }
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        AttachmentConfig {
+            max_size: 50 * 1024 * 1024,
+            allowed_extensions: [
+                "pdf", "txt", "md", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "zip", "7z",
+                "mp4", "mov", "webm",
+            ]
+            .iter()
+            .map(|extension| extension.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        ScheduleConfig {
+            enable: false,
+            cron: "0 0 22 * * *".to_string(),
+            skip_without_new_records: true,
+        }
+    }
+}
+
+impl Default for RecordSessionConfig {
+    fn default() -> Self {
+        RecordSessionConfig {
+            enable: true,
+            idle_timeout: 600,
+            check_interval: 30,
+            timeout_action: TimeoutAction::default(),
         }
     }
 }
@@ -65,11 +357,23 @@ impl Default for GitConfig {
 impl Default for DataBaseConfig {
     fn default() -> Self {
         DataBaseConfig{
+            kind: DataBaseKind::default(),
             username: "postgres".to_string(),
             password: "postgres".to_string(),
             host: "127.0.0.1".to_string(),
             port: 5432,
             database: "postgres".to_string(),
+            init_data: InitBotDataConfig::default(),
+        }
+    }
+}
+
+impl Default for InitBotDataConfig {
+    fn default() -> Self {
+        InitBotDataConfig {
+            admin: 0,
+            share_path: "share".to_string(),
+            max_title_length: 90,
         }
     }
 }
@@ -77,25 +381,78 @@ impl Default for DataBaseConfig {
 impl CoreConfig {
     pub fn init() -> Result<Self> {
         let config = CoreConfig::default();
-        if Path::try_exists(TOML_CONFIG_FILE_NAME.as_ref())? {
-            Ok(Figment::merge(
+        let figment = if Path::try_exists(TOML_CONFIG_FILE_NAME.as_ref())? {
+            Figment::merge(
                 Figment::from(Serialized::defaults(config)),
                 Toml::file(TOML_CONFIG_FILE_NAME),
             )
-                .extract()?)
         } else if Path::try_exists(YAML_CONFIG_FILE_NAME.as_ref())? {
-            Ok(Figment::from(Serialized::defaults(config))
+            Figment::from(Serialized::defaults(config))
                 .merge(Yaml::file(YAML_CONFIG_FILE_NAME))
-                .extract()?)
         } else if Path::try_exists(JSON_CONFIG_FILE_NAME.as_ref())? {
-            Ok(Figment::from(Serialized::defaults(config))
+            Figment::from(Serialized::defaults(config))
                 .merge(Json::file(JSON_CONFIG_FILE_NAME))
-                .extract()?)
         } else {
             let mut config_file: File = File::create(JSON_CONFIG_FILE_NAME)?;
             config_file.write_all(serde_json::to_string_pretty(&config)?.as_bytes())?;
             config_file.flush()?;
-            Ok(config)
+            return Ok(config);
+        };
+        CoreConfig::extract(&figment)
+    }
+
+    fn extract(figment: &Figment) -> Result<Self> {
+        let mut config: CoreConfig = figment.extract()?;
+        if let Ok(password) = figment.find_value("git.password") {
+            let token = password
+                .into_string()
+                .ok_or(anyhow!("git.password must be a string"))?;
+            config.git.migrate_password(token)?;
+        }
+        Ok(config)
+    }
+}
+
+impl GitConfig {
+    /// 旧版配置以 `username` 和 `password` 拼接 HTTPS 地址推送
+    fn migrate_password(&mut self, token: String) -> Result<()> {
+        match self.credential {
+            GitCredential::None => {
+                self.credential = GitCredential::Token {
+                    username: self.username.clone(),
+                    token,
+                };
+                Ok(())
+            }
+            _ => Err(anyhow!(
+                "git.password is replaced by git.credential, remove git.password from the config"
+            )),
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(toml: &str) -> Result<CoreConfig> {
+        let figment = Figment::from(Serialized::defaults(CoreConfig::default()))
+            .merge(Toml::string(toml));
+        CoreConfig::extract(&figment)
+    }
+
+    #[test]
+    fn legacy_git_password_becomes_token() {
+        let config = extract("[git]\nusername = \"bot\"\npassword = \"p@ss\"").unwrap();
+        let GitCredential::Token { username, token } = config.git.credential else {
+            panic!("unexpected credential: {:?}", config.git.credential);
+        };
+        assert_eq!((username.as_str(), token.as_str()), ("bot", "p@ss"));
+
+        let config = extract("[git]\nusername = \"bot\"").unwrap();
+        assert!(matches!(config.git.credential, GitCredential::None));
+
+        let both = "[git]\npassword = \"p\"\ncredential = { Token = { username = \"bot\", token = \"t\" } }";
+        assert!(extract(both).is_err());
+    }
 }
\ No newline at end of file
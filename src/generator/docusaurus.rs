@@ -0,0 +1,42 @@
+use crate::generator::template::DefaultTemplates;
+use crate::generator::{ArchiveMonth, OutputTarget};
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Docusaurus，目录索引为 index.md，侧边栏分类由 _category_.json 描述
+pub struct Docusaurus;
+
+impl OutputTarget for Docusaurus {
+    fn root_index_path(&self) -> PathBuf {
+        PathBuf::from("index.md")
+    }
+
+    fn month_index_path(&self, month: &str) -> PathBuf {
+        PathBuf::from(format!("{}/index.md", month))
+    }
+
+    fn default_templates(&self) -> DefaultTemplates {
+        DefaultTemplates {
+            root: include_str!("templates/docusaurus/root.md.j2"),
+            month: include_str!("templates/docusaurus/month.md.j2"),
+            day: include_str!("templates/docusaurus/day.md.j2"),
+        }
+    }
+
+    fn extra_files(&self, months: &[ArchiveMonth]) -> Vec<(PathBuf, String)> {
+        let mut files = vec![(
+            PathBuf::from("_category_.json"),
+            format!("{:#}\n", json!({ "label": "分享" })),
+        )];
+        for (position, month) in months.iter().enumerate() {
+            files.push((
+                PathBuf::from(format!("{}/_category_.json", month.month)),
+                format!(
+                    "{:#}\n",
+                    json!({ "label": month.month, "position": position + 1 })
+                ),
+            ));
+        }
+        files
+    }
+}
@@ -0,0 +1,29 @@
+use crate::generator::template::DefaultTemplates;
+use crate::generator::{encode_link, relative_link, OutputTarget};
+use std::path::{Path, PathBuf};
+
+/// Hugo，目录索引为 _index.md（section），排序依赖 weight
+pub struct Hugo;
+
+impl OutputTarget for Hugo {
+    fn root_index_path(&self) -> PathBuf {
+        PathBuf::from("_index.md")
+    }
+
+    fn month_index_path(&self, month: &str) -> PathBuf {
+        PathBuf::from(format!("{}/_index.md", month))
+    }
+
+    fn default_templates(&self) -> DefaultTemplates {
+        DefaultTemplates {
+            root: include_str!("templates/hugo/root.md.j2"),
+            month: include_str!("templates/hugo/month.md.j2"),
+            day: include_str!("templates/hugo/day.md.j2"),
+        }
+    }
+
+    /// Hugo 的页面以目录形式发布（`/2024-01/2024-01-01/`），需要多退一级目录
+    fn asset_link(&self, page: &Path, image: &Path) -> String {
+        format!("../{}", encode_link(&relative_link(page, image)))
+    }
+}
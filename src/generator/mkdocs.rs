@@ -0,0 +1,59 @@
+use crate::generator::template::DefaultTemplates;
+use crate::generator::{ArchiveMonth, OutputTarget};
+use std::path::PathBuf;
+
+const NAV_FILE_NAME: &str = "mkdocs-nav.yml";
+
+/// MkDocs，目录索引为 index.md，另外生成可合并进 mkdocs.yml 的 nav 片段
+pub struct MkDocs {
+    /// 分享目录相对于 docs_dir 的路径，用于 nav 片段中的页面路径
+    pub nav_prefix: String,
+}
+
+impl MkDocs {
+    fn nav_path(&self, path: &str) -> String {
+        if self.nav_prefix.is_empty() {
+            return path.to_string();
+        }
+        format!("{}/{}", self.nav_prefix.trim_end_matches('/'), path)
+    }
+}
+
+impl OutputTarget for MkDocs {
+    fn root_index_path(&self) -> PathBuf {
+        PathBuf::from("index.md")
+    }
+
+    fn month_index_path(&self, month: &str) -> PathBuf {
+        PathBuf::from(format!("{}/index.md", month))
+    }
+
+    fn default_templates(&self) -> DefaultTemplates {
+        DefaultTemplates {
+            root: include_str!("templates/mkdocs/root.md.j2"),
+            month: include_str!("templates/mkdocs/month.md.j2"),
+            day: include_str!("templates/mkdocs/day.md.j2"),
+        }
+    }
+
+    fn extra_files(&self, months: &[ArchiveMonth]) -> Vec<(PathBuf, String)> {
+        let mut nav =
+            String::from("# 由 simple-docs-bot 生成，将以下内容合并到 mkdocs.yml 的 nav 中\n");
+        nav.push_str(&format!("- 分享:\n    - {}\n", self.nav_path("index.md")));
+        for month in months {
+            nav.push_str(&format!("    - '{}':\n", month.month));
+            nav.push_str(&format!(
+                "        - {}\n",
+                self.nav_path(&format!("{}/index.md", month.month))
+            ));
+            for day in &month.days {
+                nav.push_str(&format!(
+                    "        - '{}': {}\n",
+                    day.day,
+                    self.nav_path(&format!("{}/{}.md", month.month, day.day))
+                ));
+            }
+        }
+        vec![(PathBuf::from(NAV_FILE_NAME), nav)]
+    }
+}
@@ -0,0 +1,713 @@
+use crate::bot_help::BotHelp;
+use crate::config::{GenerateConfig, OutputTargetKind};
+use crate::generator::template::{
+    DefaultTemplates, DAY_TEMPLATE, MONTH_TEMPLATE, RECORD_TEMPLATE, ROOT_TEMPLATE,
+};
+use crate::storage::{Content, Record};
+use crate::utils::forward_message::{ForwardMessage, ForwardPart};
+use crate::utils::image;
+use anyhow::Result;
+use minijinja::{context, Environment, Value};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read, read_to_string, remove_file, write};
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+mod docusaurus;
+mod hugo;
+mod mkdocs;
+mod template;
+mod vuepress_hope;
+
+/// 记录上次生成时每个文件内容的哈希，位于分享目录下
+const MANIFEST_FILE_NAME: &str = ".generate-manifest.json";
+/// 上次生成包含的记录 ID，位于分享目录下，推送时据此统计新增和删除的记录
+pub const RECORDS_FILE_NAME: &str = ".generate-records.json";
+/// 链接中除 RFC 3986 非保留字符以外都编码
+const LINK_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+pub struct ArchiveMonth {
+    /// `%Y-%m`
+    pub month: String,
+    pub days: Vec<ArchiveDay>,
+}
+
+pub struct ArchiveDay {
+    /// `%Y-%m-%d`
+    pub day: String,
+    pub records: Vec<ArchiveRecord>,
+}
+
+pub struct ArchiveRecord {
+    pub record: Record,
+    pub contents: Vec<Content>,
+}
+
+/// 静态站点输出格式，决定文件布局、默认模板以及额外的配置文件
+pub trait OutputTarget {
+    fn root_index_path(&self) -> PathBuf;
+
+    fn month_index_path(&self, month: &str) -> PathBuf;
+
+    fn day_page_path(&self, month: &str, day: &str) -> PathBuf {
+        PathBuf::from(format!("{}/{}.md", month, day))
+    }
+
+    fn default_templates(&self) -> DefaultTemplates;
+
+    /// 图片、附件相对于日期页面的链接，`page` 与 `image` 均为相对于分享目录的路径，
+    /// 附件保留原文件名，每一段都需要转义后才能放进 Markdown 链接和 HTML 属性
+    fn asset_link(&self, page: &Path, image: &Path) -> String {
+        encode_link(&relative_link(page, image))
+    }
+
+    fn extra_files(&self, _months: &[ArchiveMonth]) -> Vec<(PathBuf, String)> {
+        Vec::new()
+    }
+}
+
+pub fn output_target(kind: &OutputTargetKind) -> Box<dyn OutputTarget + Send + Sync> {
+    match kind {
+        OutputTargetKind::VuePressHope => Box::new(vuepress_hope::VuePressHope),
+        OutputTargetKind::MkDocs { nav_prefix } => Box::new(mkdocs::MkDocs {
+            nav_prefix: nav_prefix.clone(),
+        }),
+        OutputTargetKind::Hugo => Box::new(hugo::Hugo),
+        OutputTargetKind::Docusaurus => Box::new(docusaurus::Docusaurus),
+    }
+}
+
+/// 月份按时间倒序，日期与记录按时间正序，保证相同的记录生成完全相同的文件
+/// 只读取并渲染记录有变化的日期页面，只写入与磁盘上内容不同的文件，删除不再有记录的页面，
+/// 返回写入和删除的文件数
+pub async fn generate_by_records(
+    config: &GenerateConfig,
+    bot_help: &BotHelp,
+    mut records: Vec<Record>,
+) -> Result<usize> {
+    let target = output_target(&config.target);
+    records.sort_by_key(|record| record.created_at);
+    let record_ids: Vec<String> = records.iter().map(|record| record.id.clone()).collect();
+    let mut record_order_by_month: BTreeMap<String, BTreeMap<String, Vec<ArchiveRecord>>> =
+        BTreeMap::new();
+    for record in records {
+        record_order_by_month
+            .entry(record.created_at.format("%Y-%m").to_string())
+            .or_default()
+            .entry(record.created_at.format("%Y-%m-%d").to_string())
+            .or_default()
+            .push(ArchiveRecord {
+                record,
+                contents: Vec::new(),
+            });
+    }
+    let mut months: Vec<ArchiveMonth> = record_order_by_month
+        .into_iter()
+        .rev()
+        .map(|(month, days)| ArchiveMonth {
+            month,
+            days: days
+                .into_iter()
+                .map(|(day, records)| ArchiveDay { day, records })
+                .collect(),
+        })
+        .collect();
+
+    let templates =
+        template::load_templates(target.default_templates(), config.template_dir.as_deref())?;
+    let share_path = bot_help.share_path().await?;
+    let root_path = Path::new(&share_path);
+    let manifest_path = root_path.join(MANIFEST_FILE_NAME);
+    let previous = read_manifest(&manifest_path);
+    let mut manifest = Manifest::default();
+
+    let mut files = render_indexes(target.as_ref(), &templates, &months)?;
+    files.extend(target.extra_files(&months));
+    let templates_hash = content_hash(
+        format!(
+            "{:?}\n{}\n{}",
+            config.target,
+            templates.get_template(DAY_TEMPLATE)?.source(),
+            templates.get_template(RECORD_TEMPLATE)?.source()
+        )
+        .as_bytes(),
+    );
+    for month in months.iter_mut() {
+        for (day_position, day) in month.days.iter_mut().enumerate() {
+            let path = target.day_page_path(&month.month, &day.day);
+            let key = path.to_string_lossy().to_string();
+            let fingerprint = day_fingerprint(&templates_hash, day_position, day);
+            // 输入没有变化且磁盘上仍是上次写入的内容时跳过
+            let unchanged = previous.files.get(&key).filter(|hash| {
+                previous.days.get(&key) == Some(&fingerprint)
+                    && file_hash(&root_path.join(&path)).as_ref() == Some(*hash)
+            });
+            manifest.days.insert(key.clone(), fingerprint);
+            if let Some(hash) = unchanged {
+                manifest.files.insert(key, hash.clone());
+                continue;
+            }
+            for entry in day.records.iter_mut() {
+                entry.contents = bot_help.select_content(&entry.record.id).await?;
+            }
+            let page = render_day(target.as_ref(), &templates, &path, day_position, day)?;
+            files.push((path, page));
+        }
+    }
+
+    let mut written = 0;
+    for (path, content) in files {
+        let save_path = root_path.join(&path);
+        let hash = content_hash(content.as_bytes());
+        manifest
+            .files
+            .insert(path.to_string_lossy().to_string(), hash.clone());
+        // 与磁盘上的文件比较，手动修改过的文件也会被恢复
+        if file_hash(&save_path) == Some(hash) {
+            continue;
+        }
+        if let Some(parent) = save_path.parent() {
+            if !parent.exists() {
+                create_dir_all(parent)?;
+            }
+        }
+        write(save_path, content)?;
+        written += 1;
+    }
+    for key in previous.files.keys() {
+        let orphan = root_path.join(key);
+        if !manifest.files.contains_key(key) && orphan.is_file() {
+            remove_file(orphan)?;
+            written += 1;
+        }
+    }
+    if manifest != previous {
+        write(manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+    }
+    let records_path = root_path.join(RECORDS_FILE_NAME);
+    let record_ids = serde_json::to_string_pretty(&record_ids)?;
+    if read_to_string(&records_path).ok().as_ref() != Some(&record_ids) {
+        write(records_path, record_ids)?;
+    }
+    Ok(written)
+}
+
+/// 上次生成的结果，键为相对于分享目录的路径
+#[derive(Serialize, Deserialize, Default, PartialEq)]
+struct Manifest {
+    /// 每个生成文件写入时内容的哈希
+    files: BTreeMap<String, String>,
+    /// 每个日期页面的输入指纹，未变化时不再读取内容和渲染
+    days: BTreeMap<String, String>,
+}
+
+fn read_manifest(path: &Path) -> Manifest {
+    if !path.exists() {
+        return Manifest::default();
+    }
+    match read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|manifest| Ok(serde_json::from_str(&manifest)?))
+    {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            warn!("Read Generate Manifest Error, Regenerate All: {:?}", e);
+            Manifest::default()
+        }
+    }
+}
+
+fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+fn file_hash(path: &Path) -> Option<String> {
+    read(path).ok().map(|content| content_hash(&content))
+}
+
+/// 日期页面只取决于模板、页面位置和记录，记录的内容变化时 `revision` 会递增
+fn day_fingerprint(templates_hash: &str, day_position: usize, day: &ArchiveDay) -> String {
+    let mut source = format!("{}\n{}\n", templates_hash, day_position);
+    for entry in &day.records {
+        let record = &entry.record;
+        source.push_str(&format!(
+            "{:?}\n",
+            (
+                &record.id,
+                &record.title,
+                &record.remark,
+                record.created_at.to_rfc3339(),
+                record.revision,
+            )
+        ));
+    }
+    content_hash(source.as_bytes())
+}
+
+/// 根目录与各月份的索引，只依赖月份和日期列表
+fn render_indexes(
+    target: &dyn OutputTarget,
+    templates: &Environment<'static>,
+    months: &[ArchiveMonth],
+) -> Result<Vec<(PathBuf, String)>> {
+    let root_index_path = target.root_index_path();
+    let month_links: Vec<_> = months
+        .iter()
+        .map(|month| {
+            context! {
+                month => month.month,
+                link => relative_link(&root_index_path, &target.month_index_path(&month.month)),
+            }
+        })
+        .collect();
+    let mut files = vec![(
+        root_index_path,
+        templates
+            .get_template(ROOT_TEMPLATE)?
+            .render(context! { months => month_links })?,
+    )];
+    for (month_position, month) in months.iter().enumerate() {
+        let month_index_path = target.month_index_path(&month.month);
+        let day_links: Vec<_> = month
+            .days
+            .iter()
+            .map(|day| {
+                context! {
+                    day => day.day,
+                    link => relative_link(
+                        &month_index_path,
+                        &target.day_page_path(&month.month, &day.day),
+                    ),
+                }
+            })
+            .collect();
+        files.push((
+            month_index_path,
+            templates.get_template(MONTH_TEMPLATE)?.render(context! {
+                month => month.month,
+                position => month_position + 1,
+                days => day_links,
+            })?,
+        ));
+    }
+    Ok(files)
+}
+
+fn render_day(
+    target: &dyn OutputTarget,
+    templates: &Environment<'static>,
+    day_page_path: &Path,
+    day_position: usize,
+    day: &ArchiveDay,
+) -> Result<String> {
+    let mut records = Vec::new();
+    for entry in &day.records {
+        records.push(render_record(target, templates, day_page_path, entry)?);
+    }
+    Ok(templates.get_template(DAY_TEMPLATE)?.render(context! {
+        day => day.day,
+        title => day.records[0].record.created_at.format("%Y年%m月%d").to_string(),
+        position => day_position + 1,
+        records => records,
+    })?)
+}
+
+fn render_record(
+    target: &dyn OutputTarget,
+    templates: &Environment<'static>,
+    day_page_path: &Path,
+    entry: &ArchiveRecord,
+) -> Result<String> {
+    let contents: Vec<_> = entry
+        .contents
+        .iter()
+        .map(|content| {
+            let link = match content.content_type.as_str() {
+                "image" | "file" | "video" => target.asset_link(
+                    day_page_path,
+                    &image::image_path(&entry.record, &content.content),
+                ),
+                _ => String::new(),
+            };
+            let forward = match content.content_type.as_str() {
+                "forward" => forward_context(target, day_page_path, &content.content),
+                _ => Vec::new(),
+            };
+            context! {
+                type => content.content_type,
+                content => content.content,
+                name => content.content.rsplit('/').next().map(escape_link_text),
+                link => link,
+                forward => forward,
+            }
+        })
+        .collect();
+    Ok(templates.get_template(RECORD_TEMPLATE)?.render(context! {
+        title => entry.record.title,
+        remark => entry.record.remark,
+        contents => contents,
+    })?)
+}
+
+/// 合并转发内容：`[{sender, parts: [{type, lines, link}]}]`，文本按行拆分便于逐行引用
+fn forward_context(target: &dyn OutputTarget, day_page_path: &Path, content: &str) -> Vec<Value> {
+    let messages: Vec<ForwardMessage> = match serde_json::from_str(content) {
+        Ok(messages) => messages,
+        Err(e) => {
+            warn!("Parse Forward Content Error: {:?}", e);
+            return Vec::new();
+        }
+    };
+    messages
+        .iter()
+        .map(|message| {
+            let parts: Vec<Value> = message
+                .parts
+                .iter()
+                .map(|part| match part {
+                    ForwardPart::Text { text } => context! {
+                        type => "text",
+                        lines => text.lines().collect::<Vec<_>>(),
+                    },
+                    ForwardPart::Image { path } => context! {
+                        type => "image",
+                        link => target.asset_link(day_page_path, Path::new(path)),
+                    },
+                })
+                .collect();
+            context! {
+                sender => message.sender,
+                parts => parts,
+            }
+        })
+        .collect()
+}
+
+/// 对链接的每一段做百分号编码，保留 `/`
+pub fn encode_link(link: &str) -> String {
+    link.split('/')
+        .map(|segment| utf8_percent_encode(segment, LINK_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 转义 Markdown 链接文本中的方括号
+fn escape_link_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// 计算 `to` 相对于页面 `from` 所在目录的链接，两者均为相对于分享根目录的路径
+pub fn relative_link(from: &Path, to: &Path) -> String {
+    let from_dir: Vec<Component> = from
+        .parent()
+        .map(|dir| dir.components().collect())
+        .unwrap_or_default();
+    let to_components: Vec<Component> = to.components().collect();
+    let common = from_dir
+        .iter()
+        .zip(to_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from_dir.len() - common];
+    parts.extend(
+        to_components[common..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestBot;
+    use chrono::{Local, TimeZone};
+    use std::fs;
+
+    fn record(id: &str, title: &str, (y, m, d, h): (i32, u32, u32, u32)) -> Record {
+        Record {
+            id: id.to_string(),
+            title: title.to_string(),
+            remark: None,
+            created_at: Local.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap(),
+            group_id: 0,
+            revision: 0,
+        }
+    }
+
+    fn records() -> Vec<Record> {
+        vec![
+            record("c", "第三", (2026, 10, 2, 9)),
+            record("a", "第一", (2026, 9, 30, 8)),
+            record("d", "第二", (2026, 10, 2, 8)),
+            record("b", "第零", (2026, 10, 1, 20)),
+        ]
+    }
+
+    fn read_tree(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        let mut tree = BTreeMap::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                tree.extend(read_tree(&path));
+            } else {
+                tree.insert(path.clone(), fs::read(&path).unwrap());
+            }
+        }
+        tree
+    }
+
+    #[tokio::test]
+    async fn generated_pages_are_sorted_and_reproducible() {
+        let bot = TestBot::new().await;
+        let share_path = bot.share_path();
+        let config = GenerateConfig::default();
+
+        generate_by_records(&config, &bot.bot_help, records())
+            .await
+            .unwrap();
+        let first = read_tree(&share_path);
+        let mut reversed = records();
+        reversed.reverse();
+        generate_by_records(&config, &bot.bot_help, reversed)
+            .await
+            .unwrap();
+        assert_eq!(first, read_tree(&share_path));
+
+        let root = fs::read_to_string(share_path.join("README.md")).unwrap();
+        assert!(
+            root.ends_with("- [2026-10](2026-10/README.md)\n\n- [2026-09](2026-09/README.md)\n")
+        );
+        let month = fs::read_to_string(share_path.join("2026-10/README.md")).unwrap();
+        assert!(month.ends_with("- [2026-10-01](2026-10-01.md)\n\n- [2026-10-02](2026-10-02.md)\n"));
+        let day = fs::read_to_string(share_path.join("2026-10/2026-10-02.md")).unwrap();
+        assert!(day.find("## 第二").unwrap() < day.find("## 第三").unwrap());
+    }
+
+    #[tokio::test]
+    async fn each_target_writes_its_own_layout() {
+        let cases = [
+            (
+                OutputTargetKind::MkDocs {
+                    nav_prefix: "share".to_string(),
+                },
+                vec![
+                    "index.md",
+                    "2026-10/index.md",
+                    "2026-10/2026-10-01.md",
+                    "mkdocs-nav.yml",
+                ],
+            ),
+            (
+                OutputTargetKind::Hugo,
+                vec!["_index.md", "2026-10/_index.md", "2026-10/2026-10-01.md"],
+            ),
+            (
+                OutputTargetKind::Docusaurus,
+                vec![
+                    "index.md",
+                    "_category_.json",
+                    "2026-10/_category_.json",
+                    "2026-10/2026-10-01.md",
+                ],
+            ),
+        ];
+        for (target, expected_files) in cases {
+            let bot = TestBot::new().await;
+            let share_path = bot.share_path();
+            let config = GenerateConfig {
+                target,
+                template_dir: None,
+            };
+            generate_by_records(&config, &bot.bot_help, records())
+                .await
+                .unwrap();
+            for file in expected_files {
+                assert!(
+                    share_path.join(file).is_file(),
+                    "{:?} missing {}",
+                    config.target,
+                    file
+                );
+            }
+            assert!(!share_path.join("README.md").exists());
+        }
+
+        let bot = TestBot::new().await;
+        let config = GenerateConfig {
+            target: OutputTargetKind::MkDocs {
+                nav_prefix: "share".to_string(),
+            },
+            template_dir: None,
+        };
+        generate_by_records(&config, &bot.bot_help, records())
+            .await
+            .unwrap();
+        let nav = fs::read_to_string(bot.share_path().join("mkdocs-nav.yml")).unwrap();
+        assert!(nav.contains("    - '2026-10':\n        - share/2026-10/index.md\n        - '2026-10-01': share/2026-10/2026-10-01.md\n"));
+        assert!(nav.find("'2026-10'").unwrap() < nav.find("'2026-09'").unwrap());
+    }
+
+    #[tokio::test]
+    async fn template_dir_overrides_only_present_templates() {
+        let bot = TestBot::new().await;
+        let template_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            template_dir.path().join("day.md.j2"),
+            "# {{ day }} ({{ records | length }})\n{% for record in records %}{{ record }}{% endfor %}\n",
+        )
+        .unwrap();
+        fs::write(
+            template_dir.path().join("record.md.j2"),
+            "* {{ title }}{% if remark %} - {{ remark }}{% endif %}\n\n",
+        )
+        .unwrap();
+        let config = GenerateConfig {
+            target: OutputTargetKind::VuePressHope,
+            template_dir: Some(template_dir.path().to_string_lossy().to_string()),
+        };
+        generate_by_records(&config, &bot.bot_help, records())
+            .await
+            .unwrap();
+
+        let share_path = bot.share_path();
+        let day = fs::read_to_string(share_path.join("2026-10/2026-10-02.md")).unwrap();
+        assert_eq!(day, "# 2026-10-02 (2)\n* 第二\n* 第三\n");
+        let root = fs::read_to_string(share_path.join("README.md")).unwrap();
+        assert!(root.starts_with("---\ntitle: 分享\nicon: comments\n"));
+    }
+
+    #[tokio::test]
+    async fn unchanged_pages_are_not_rewritten() {
+        let bot = TestBot::new().await;
+        let config = GenerateConfig::default();
+        let first = generate_by_records(&config, &bot.bot_help, records()).await.unwrap();
+        assert_eq!(first, 6);
+        assert_eq!(generate_by_records(&config, &bot.bot_help, records()).await.unwrap(), 0);
+
+        let mut more = records();
+        more.push(record("e", "第四", (2026, 10, 3, 8)));
+        assert_eq!(generate_by_records(&config, &bot.bot_help, more.clone()).await.unwrap(), 2);
+
+        let share_path = bot.share_path();
+        fs::remove_file(share_path.join("2026-09/2026-09-30.md")).unwrap();
+        assert_eq!(generate_by_records(&config, &bot.bot_help, more.clone()).await.unwrap(), 1);
+        assert!(share_path.join("2026-09/2026-09-30.md").is_file());
+
+        let day_path = share_path.join("2026-10/2026-10-01.md");
+        let day = fs::read_to_string(&day_path).unwrap();
+        fs::write(&day_path, "手动修改").unwrap();
+        assert_eq!(generate_by_records(&config, &bot.bot_help, more).await.unwrap(), 1);
+        assert_eq!(fs::read_to_string(&day_path).unwrap(), day);
+    }
+
+    #[tokio::test]
+    async fn pages_without_records_are_removed() {
+        let bot = TestBot::new().await;
+        let config = GenerateConfig::default();
+        generate_by_records(&config, &bot.bot_help, records()).await.unwrap();
+
+        let remaining: Vec<Record> = records()
+            .into_iter()
+            .filter(|record| record.id != "a")
+            .collect();
+        // 根目录索引更新，2026-09 的日期页面和月份索引被删除
+        assert_eq!(generate_by_records(&config, &bot.bot_help, remaining).await.unwrap(), 3);
+        let share_path = bot.share_path();
+        assert!(!share_path.join("2026-09/2026-09-30.md").exists());
+        assert!(!share_path.join("2026-09/README.md").exists());
+        let manifest = fs::read_to_string(share_path.join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(!manifest.contains("2026-09"));
+    }
+
+    #[tokio::test]
+    async fn changed_content_rerenders_the_day() {
+        let bot = TestBot::new().await;
+        let config = GenerateConfig::default();
+        let uuid = bot
+            .bot_help
+            .insert_new_record(0, "标题".to_string())
+            .await
+            .unwrap();
+        let records = bot.bot_help.select_all_records().await.unwrap();
+        generate_by_records(&config, &bot.bot_help, records).await.unwrap();
+
+        bot.bot_help
+            .record_content(uuid, "新内容".to_string(), "text".to_string())
+            .await
+            .unwrap();
+        let records = bot.bot_help.select_all_records().await.unwrap();
+        let created_at = records[0].created_at;
+        assert_eq!(generate_by_records(&config, &bot.bot_help, records).await.unwrap(), 1);
+        let day_path = format!(
+            "{}/{}.md",
+            created_at.format("%Y-%m"),
+            created_at.format("%Y-%m-%d")
+        );
+        let day = fs::read_to_string(bot.share_path().join(day_path)).unwrap();
+        assert!(day.contains("新内容"), "{}", day);
+    }
+
+    #[tokio::test]
+    async fn image_links_are_relative_to_the_day_page() {
+        let bot = TestBot::new().await;
+        for content in ["2026-10/a/late.png", "legacy.png"] {
+            bot.bot_help
+                .record_content("a".to_string(), content.to_string(), "image".to_string())
+                .await
+                .unwrap();
+        }
+        let cases = [
+            (OutputTargetKind::VuePressHope, "../2026-10/a/late.png", "a/legacy.png"),
+            (OutputTargetKind::Hugo, "../../2026-10/a/late.png", "../a/legacy.png"),
+        ];
+        for (target, late, legacy) in cases {
+            let config = GenerateConfig {
+                target,
+                template_dir: None,
+            };
+            generate_by_records(&config, &bot.bot_help, records()).await.unwrap();
+            let day = fs::read_to_string(bot.share_path().join("2026-09/2026-09-30.md")).unwrap();
+            assert!(day.contains(&format!("![image]({})\n\n![image]({})\n", late, legacy)), "{}", day);
+        }
+    }
+
+    #[tokio::test]
+    async fn attachment_links_are_escaped() {
+        let bot = TestBot::new().await;
+        for (content, content_type) in [
+            ("2026-09/a/会议 纪要 [终版] (1).pdf", "file"),
+            ("2026-09/a/\"演示\".mp4", "video"),
+        ] {
+            bot.bot_help
+                .record_content(
+                    "a".to_string(),
+                    content.to_string(),
+                    content_type.to_string(),
+                )
+                .await
+                .unwrap();
+        }
+        generate_by_records(&GenerateConfig::default(), &bot.bot_help, records())
+            .await
+            .unwrap();
+        let day = fs::read_to_string(bot.share_path().join("2026-09/2026-09-30.md")).unwrap();
+        assert!(
+            day.contains("[会议 纪要 \\[终版\\] (1).pdf](a/%E4%BC%9A%E8%AE%AE%20%E7%BA%AA%E8%A6%81%20%5B%E7%BB%88%E7%89%88%5D%20%281%29.pdf)\n"),
+            "{}",
+            day
+        );
+        assert!(
+            day.contains("<video src=\"a/%22%E6%BC%94%E7%A4%BA%22.mp4\" controls></video>"),
+            "{}",
+            day
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::Result;
+use minijinja::{AutoEscape, Environment};
+use std::fs::read_to_string;
+use std::path::Path;
+
+pub const ROOT_TEMPLATE: &str = "root.md.j2";
+pub const MONTH_TEMPLATE: &str = "month.md.j2";
+pub const DAY_TEMPLATE: &str = "day.md.j2";
+pub const RECORD_TEMPLATE: &str = "record.md.j2";
+
+const DEFAULT_RECORD_TEMPLATE: &str = include_str!("templates/record.md.j2");
+
+/// 输出格式自带的默认模板
+pub struct DefaultTemplates {
+    pub root: &'static str,
+    pub month: &'static str,
+    pub day: &'static str,
+}
+
+/// 模板目录中存在同名文件时使用该文件，否则使用默认模板；与 Jinja2 一致，模板末尾的一个换行会被去掉
+pub fn load_templates(
+    defaults: DefaultTemplates,
+    template_dir: Option<&str>,
+) -> Result<Environment<'static>> {
+    let mut env = Environment::new();
+    env.set_auto_escape_callback(|_| AutoEscape::None);
+    let templates = [
+        (ROOT_TEMPLATE, defaults.root),
+        (MONTH_TEMPLATE, defaults.month),
+        (DAY_TEMPLATE, defaults.day),
+        (RECORD_TEMPLATE, DEFAULT_RECORD_TEMPLATE),
+    ];
+    for (name, default) in templates {
+        let source = match template_dir.map(|dir| Path::new(dir).join(name)) {
+            Some(path) if path.exists() => read_to_string(path)?,
+            _ => default.to_string(),
+        };
+        env.add_template_owned(name, source)?;
+    }
+    Ok(env)
+}
@@ -0,0 +1,24 @@
+use crate::generator::template::DefaultTemplates;
+use crate::generator::OutputTarget;
+use std::path::PathBuf;
+
+/// VuePress Theme Hope，目录索引为 README.md
+pub struct VuePressHope;
+
+impl OutputTarget for VuePressHope {
+    fn root_index_path(&self) -> PathBuf {
+        PathBuf::from("README.md")
+    }
+
+    fn month_index_path(&self, month: &str) -> PathBuf {
+        PathBuf::from(format!("{}/README.md", month))
+    }
+
+    fn default_templates(&self) -> DefaultTemplates {
+        DefaultTemplates {
+            root: include_str!("templates/vuepress_hope/root.md.j2"),
+            month: include_str!("templates/vuepress_hope/month.md.j2"),
+            day: include_str!("templates/vuepress_hope/day.md.j2"),
+        }
+    }
+}
@@ -13,9 +13,16 @@ use tracing::{debug, error, warn};
 
 mod bot_help;
 mod config;
+mod generator;
 mod log;
 mod message_handle;
+mod role;
+mod schedule;
+mod session;
 mod status;
+mod storage;
+#[cfg(test)]
+mod test_utils;
 pub(crate) mod utils;
 
 #[tokio::main]
@@ -27,6 +34,13 @@ pub async fn main() -> Result<()> {
     let mut receiver = ws_connect.subscribe().await;
 
     utils::git::git_init(&config.git)?;
+    session::start_expire_task(config.record_session.clone(), bot_help.clone());
+    schedule::start_publish_task(
+        config.schedule.clone(),
+        config.generate.clone(),
+        config.git.clone(),
+        bot_help.clone(),
+    )?;
 
     loop {
         match receiver.recv().await? {
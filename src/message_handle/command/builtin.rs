@@ -0,0 +1,309 @@
+use super::{ArgKind, ArgSpec, Command, CommandContext, CommandSpec, Scope, REGISTRY};
+use crate::message_handle::group::{generate, record};
+use crate::message_handle::private;
+use crate::role::{self, Role};
+use anyhow::Result;
+use async_trait::async_trait;
+use onebot_v11::api::payload::ApiPayload;
+
+pub fn commands() -> Vec<Box<dyn Command>> {
+    vec![
+        Box::new(Record),
+        Box::new(Generate),
+        Box::new(List),
+        Box::new(Undo),
+        Box::new(Git),
+        Box::new(Reset),
+        Box::new(RepairImages),
+        Box::new(Grant),
+        Box::new(Revoke),
+        Box::new(Roles),
+        Box::new(Help),
+    ]
+}
+
+/// 开始记录；回复一条消息时以该消息为记录内容
+struct Record;
+
+#[async_trait]
+impl Command for Record {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["记录", "record", "rc"],
+            scope: Scope::Group,
+            role: Role::Recorder,
+            args: &[],
+            description: "开始记录，回复一条消息时以该消息为记录内容",
+        }
+    }
+
+    fn records(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        match ctx.reply_to.clone() {
+            Some(message_id) => {
+                record::handle_reply_record(ctx.session_key()?, message_id, ctx.bot_help).await
+            }
+            None => record::handle_record_start(&ctx).await,
+        }
+    }
+}
+
+struct Generate;
+
+#[async_trait]
+impl Command for Generate {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["生成", "generate", "gen"],
+            scope: Scope::Group,
+            role: Role::Recorder,
+            args: &[],
+            description: "生成记录文件",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        generate::handle_generate(&ctx).await
+    }
+}
+
+struct List;
+
+#[async_trait]
+impl Command for List {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["已记录", "list", "ls"],
+            scope: Scope::Group,
+            role: Role::Viewer,
+            args: &[ArgSpec {
+                name: "日期",
+                kind: ArgKind::Period,
+                required: false,
+            }],
+            description: "列出某天或某月的记录，默认为今日",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        record::handle_record_select(&ctx).await
+    }
+}
+
+struct Undo;
+
+#[async_trait]
+impl Command for Undo {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["撤销", "undo"],
+            scope: Scope::Group,
+            role: Role::Admin,
+            args: &[],
+            description: "删除本群最近的一条记录",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        record::handle_record_undo(&ctx).await
+    }
+}
+
+struct Git;
+
+#[async_trait]
+impl Command for Git {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["git"],
+            scope: Scope::Private,
+            role: Role::Admin,
+            args: &[],
+            description: "提交并推送记录文件",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        private::handle_git(&ctx).await
+    }
+}
+
+struct Reset;
+
+#[async_trait]
+impl Command for Reset {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["reset"],
+            scope: Scope::Private,
+            role: Role::Admin,
+            args: &[],
+            description: "清空所有进行中的记录",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        ctx.bot_help.sessions.clear().await;
+        Ok(None)
+    }
+}
+
+struct RepairImages;
+
+#[async_trait]
+impl Command for RepairImages {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["修复图片", "repair-images"],
+            scope: Scope::Private,
+            role: Role::Admin,
+            args: &[],
+            description: "把旧记录的图片移动到记录所在月份的目录",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        private::handle_repair_images(&ctx).await
+    }
+}
+
+struct Grant;
+
+#[async_trait]
+impl Command for Grant {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["授权", "grant"],
+            scope: Scope::Private,
+            role: Role::Admin,
+            args: &[
+                ArgSpec {
+                    name: "QQ号",
+                    kind: ArgKind::Integer,
+                    required: true,
+                },
+                ArgSpec {
+                    name: "身份",
+                    kind: ArgKind::Role,
+                    required: true,
+                },
+                ArgSpec {
+                    name: "群号",
+                    kind: ArgKind::Group,
+                    required: false,
+                },
+            ],
+            description: "设置用户在某个群或全局的身份",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        private::handle_grant(&ctx).await
+    }
+}
+
+struct Revoke;
+
+#[async_trait]
+impl Command for Revoke {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["取消授权", "revoke"],
+            scope: Scope::Private,
+            role: Role::Admin,
+            args: &[
+                ArgSpec {
+                    name: "QQ号",
+                    kind: ArgKind::Integer,
+                    required: true,
+                },
+                ArgSpec {
+                    name: "群号",
+                    kind: ArgKind::Group,
+                    required: false,
+                },
+            ],
+            description: "取消用户在某个群或全局的身份",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        private::handle_revoke(&ctx).await
+    }
+}
+
+struct Roles;
+
+#[async_trait]
+impl Command for Roles {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["身份列表", "roles"],
+            scope: Scope::Private,
+            role: Role::Admin,
+            args: &[],
+            description: "列出所有已分配的身份",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        private::handle_list_roles(&ctx).await
+    }
+}
+
+/// 列出调用者可用的指令，或显示某个指令的详细用法
+struct Help;
+
+#[async_trait]
+impl Command for Help {
+    fn spec(&self) -> &CommandSpec {
+        &CommandSpec {
+            names: &["帮助", "help"],
+            scope: Scope::Any,
+            role: Role::Viewer,
+            args: &[ArgSpec {
+                name: "指令",
+                kind: ArgKind::Text,
+                required: false,
+            }],
+            description: "查看可用指令，或某个指令的详细用法",
+        }
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+        // 私聊中带群号参数的指令按调用者在该群的身份判断权限，只有群内身份时也列出
+        let group_role = match ctx.group_id {
+            Some(_) => None,
+            None => role::highest_group_role(&ctx.bot_help, ctx.user_id).await?,
+        };
+        let role = group_role.map_or(ctx.role, |group_role| group_role.max(ctx.role));
+        let group_scoped = |spec: &CommandSpec| spec.role > ctx.role;
+        let mut available = REGISTRY.available(ctx.scope(), role).filter(|command| {
+            let spec = command.spec();
+            !group_scoped(spec) || spec.args.iter().any(|arg| arg.kind == ArgKind::Group)
+        });
+        if let Some(name) = ctx.text(0) {
+            return match available.find(|command| command.spec().names.contains(&name)) {
+                Some(command) => ctx.reply_text(command.spec().detail()),
+                None => ctx.reply_text(format!("没有找到指令：{}", name)),
+            };
+        }
+        let mut reply = String::from("可用指令：");
+        for command in available {
+            let spec = command.spec();
+            reply.push_str(&format!("\n{}", spec.name()));
+            if spec.names.len() > 1 {
+                reply.push_str(&format!("（{}）", spec.names[1..].join("、")));
+            }
+            reply.push_str(&format!("：{}", spec.description));
+            if group_scoped(spec) {
+                reply.push_str("（需指定你有权限的群号）");
+            }
+        }
+        reply.push_str("\n发送“帮助 <指令>”查看详细用法");
+        ctx.reply_text(reply)
+    }
+}
@@ -0,0 +1,565 @@
+use crate::bot_help::BotHelp;
+use crate::config::CoreConfig;
+use crate::role::{self, Role};
+use crate::session::SessionKey;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{Months, NaiveDate};
+use onebot_v11::api::payload::{ApiPayload, SendGroupMsg, SendPrivateMsg};
+use onebot_v11::event::message::{GroupMessage, PrivateMessage};
+use onebot_v11::MessageSegment;
+use std::sync::{Arc, LazyLock};
+use tracing::{info, warn};
+
+mod builtin;
+
+/// 指令可以在哪里使用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Group,
+    Private,
+    Any,
+}
+
+impl Scope {
+    fn includes(self, scope: Scope) -> bool {
+        self == Scope::Any || self == scope
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// `YYYY-MM` 或 `YYYY-MM-DD`
+    Period,
+    Text,
+    Integer,
+    Role,
+    /// 正整数群号，私聊中按调用者在该群的身份判断权限
+    Group,
+}
+
+impl ArgKind {
+    fn format(self) -> &'static str {
+        match self {
+            ArgKind::Period => "YYYY-MM 或 YYYY-MM-DD",
+            ArgKind::Text => "文本",
+            ArgKind::Integer => "数字",
+            ArgKind::Role => "查看者、记录者、管理员或所有者",
+            ArgKind::Group => "群号",
+        }
+    }
+}
+
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub required: bool,
+}
+
+/// 日期范围，`end` 不包含在内
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Period {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgValue {
+    Period(Period),
+    Text(String),
+    Integer(i64),
+    Role(Role),
+}
+
+pub struct CommandSpec {
+    /// 第一个为主名称，其余为别名
+    pub names: &'static [&'static str],
+    pub scope: Scope,
+    pub role: Role,
+    pub args: &'static [ArgSpec],
+    /// 一句话说明，显示在帮助中
+    pub description: &'static str,
+}
+
+impl CommandSpec {
+    pub fn name(&self) -> &'static str {
+        self.names[0]
+    }
+
+    /// 如 `已记录 [日期]`
+    pub fn usage(&self) -> String {
+        let mut usage = self.name().to_string();
+        for arg in self.args {
+            if arg.required {
+                usage.push_str(&format!(" <{}>", arg.name));
+            } else {
+                usage.push_str(&format!(" [{}]", arg.name));
+            }
+        }
+        usage
+    }
+
+    /// `帮助 <指令>` 显示的详细用法
+    pub fn detail(&self) -> String {
+        let mut detail = format!("用法：{}\n", self.usage());
+        if self.names.len() > 1 {
+            detail.push_str(&format!("别名：{}\n", self.names[1..].join("、")));
+        }
+        detail.push_str(&format!("说明：{}", self.description));
+        for arg in self.args {
+            let optional = if arg.required { "" } else { "（可选）" };
+            detail.push_str(&format!(
+                "\n{}：{}{}",
+                arg.name,
+                arg.kind.format(),
+                optional
+            ));
+        }
+        detail
+    }
+}
+
+pub struct CommandContext<'a> {
+    pub config: &'a CoreConfig,
+    pub bot_help: Arc<BotHelp>,
+    pub user_id: i64,
+    pub role: Role,
+    /// 私聊时为 `None`
+    pub group_id: Option<i64>,
+    /// 指令回复的消息 ID
+    pub reply_to: Option<String>,
+    /// 与 `CommandSpec::args` 按位置对应，省略的可选参数不在其中
+    pub args: Vec<ArgValue>,
+}
+
+impl CommandContext<'_> {
+    pub fn session_key(&self) -> Result<SessionKey> {
+        let group_id = self.group_id.ok_or(anyhow!("not a group command"))?;
+        Ok(SessionKey {
+            group_id,
+            user_id: self.user_id,
+        })
+    }
+
+    pub fn period(&self, index: usize) -> Option<&Period> {
+        match self.args.get(index) {
+            Some(ArgValue::Period(period)) => Some(period),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, index: usize) -> Option<&str> {
+        match self.args.get(index) {
+            Some(ArgValue::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, index: usize) -> Option<i64> {
+        match self.args.get(index) {
+            Some(ArgValue::Integer(integer)) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    pub fn role(&self, index: usize) -> Option<Role> {
+        match self.args.get(index) {
+            Some(ArgValue::Role(role)) => Some(*role),
+            _ => None,
+        }
+    }
+
+    /// 私聊中为 `Scope::Private`，群聊中为 `Scope::Group`
+    pub fn scope(&self) -> Scope {
+        match self.group_id {
+            Some(_) => Scope::Group,
+            None => Scope::Private,
+        }
+    }
+
+    /// 回复到指令来源的群聊或私聊
+    pub fn reply(&self, message: Vec<MessageSegment>) -> ApiPayload {
+        match self.group_id {
+            Some(group_id) => ApiPayload::SendGroupMsg(SendGroupMsg {
+                group_id,
+                message,
+                auto_escape: false,
+            }),
+            None => ApiPayload::SendPrivateMsg(SendPrivateMsg {
+                user_id: self.user_id,
+                message,
+                auto_escape: false,
+            }),
+        }
+    }
+
+    pub fn reply_text(&self, text: impl Into<String>) -> Result<Option<Vec<ApiPayload>>> {
+        Ok(Some(vec![self.reply(vec![MessageSegment::text(text)])]))
+    }
+}
+
+#[async_trait]
+pub trait Command: Send + Sync {
+    fn spec(&self) -> &CommandSpec;
+
+    /// 开始记录的指令受 `access` 中群和用户名单限制
+    fn records(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>>;
+}
+
+pub struct CommandRegistry {
+    commands: Vec<Box<dyn Command>>,
+}
+
+static REGISTRY: LazyLock<CommandRegistry> = LazyLock::new(CommandRegistry::default);
+
+impl CommandRegistry {
+    pub fn new(commands: Vec<Box<dyn Command>>) -> Self {
+        CommandRegistry { commands }
+    }
+
+    /// 按名称或别名查找，同名指令按使用范围区分
+    pub fn find(&self, name: &str, scope: Scope) -> Option<&dyn Command> {
+        self.available(scope, Role::Owner)
+            .find(|command| command.spec().names.contains(&name))
+    }
+
+    /// 在 `scope` 中以 `role` 身份可以使用的指令
+    pub fn available(&self, scope: Scope, role: Role) -> impl Iterator<Item = &dyn Command> {
+        self.commands
+            .iter()
+            .map(|command| command.as_ref())
+            .filter(move |command| {
+                command.spec().scope.includes(scope) && command.spec().role <= role
+            })
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        CommandRegistry::new(builtin::commands())
+    }
+}
+
+/// 一条消息解析出的指令
+#[derive(Debug, PartialEq)]
+struct Invocation {
+    name: String,
+    args: Vec<String>,
+    reply_to: Option<String>,
+}
+
+impl Invocation {
+    fn parse(text: &str, reply_to: Option<String>) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        Some(Invocation {
+            name: words.next()?.to_string(),
+            args: words.map(str::to_string).collect(),
+            reply_to,
+        })
+    }
+}
+
+/// 群聊中的指令：`@机器人 指令 参数…` 或 `前缀指令 参数…`，@ 可以在消息任意位置，可以回复一条消息
+fn group_invocation(message: &GroupMessage, prefix: Option<&str>) -> Option<Invocation> {
+    let mut reply_to = None;
+    let mut mentioned = false;
+    let mut texts = Vec::new();
+    for segment in &message.message {
+        match segment {
+            MessageSegment::Reply { data } => reply_to = Some(data.id.clone()),
+            MessageSegment::At { data } if data.qq == message.self_id.to_string() => {
+                mentioned = true
+            }
+            MessageSegment::Text { data } => texts.push(data.text.as_str()),
+            _ => return None,
+        }
+    }
+    let text = texts.join(" ");
+    match strip_prefix(&text, prefix) {
+        Some(command) => Invocation::parse(command, reply_to),
+        None if mentioned => Invocation::parse(&text, reply_to),
+        None => None,
+    }
+}
+
+/// 私聊中的指令：只包含文本的消息，前缀可以省略
+fn private_invocation(message: &PrivateMessage, prefix: Option<&str>) -> Option<Invocation> {
+    let mut texts = Vec::new();
+    for segment in &message.message {
+        let MessageSegment::Text { data } = segment else {
+            return None;
+        };
+        texts.push(data.text.as_str());
+    }
+    let text = texts.join(" ");
+    Invocation::parse(strip_prefix(&text, prefix).unwrap_or(&text), None)
+}
+
+fn strip_prefix<'a>(text: &'a str, prefix: Option<&str>) -> Option<&'a str> {
+    let prefix = prefix.filter(|prefix| !prefix.is_empty())?;
+    text.trim_start().strip_prefix(prefix)
+}
+
+fn parse_args(spec: &CommandSpec, args: &[String]) -> Result<Vec<ArgValue>, String> {
+    if args.len() > spec.args.len() {
+        return Err(format!("参数过多\n用法：{}", spec.usage()));
+    }
+    let mut values = Vec::new();
+    for (index, arg_spec) in spec.args.iter().enumerate() {
+        let Some(arg) = args.get(index) else {
+            if arg_spec.required {
+                return Err(format!(
+                    "缺少参数：{}\n用法：{}",
+                    arg_spec.name,
+                    spec.usage()
+                ));
+            }
+            break;
+        };
+        let value = match arg_spec.kind {
+            ArgKind::Period => parse_period(arg).map(ArgValue::Period),
+            ArgKind::Text => Some(ArgValue::Text(arg.clone())),
+            ArgKind::Integer => arg.parse().ok().map(ArgValue::Integer),
+            ArgKind::Role => arg.parse().ok().map(ArgValue::Role),
+            ArgKind::Group => arg
+                .parse()
+                .ok()
+                .filter(|group_id| *group_id > 0)
+                .map(ArgValue::Integer),
+        };
+        match value {
+            Some(value) => values.push(value),
+            None => {
+                return Err(format!(
+                    "参数格式错误：{}\n用法：{}",
+                    arg_spec.name,
+                    spec.usage()
+                ))
+            }
+        }
+    }
+    Ok(values)
+}
+
+fn parse_period(arg: &str) -> Option<Period> {
+    if let Ok(day) = NaiveDate::parse_from_str(arg, "%Y-%m-%d") {
+        return Some(Period {
+            start: day,
+            end: day.succ_opt()?,
+            label: day.format("%Y-%m-%d").to_string(),
+        });
+    }
+    let month = NaiveDate::parse_from_str(&format!("{}-01", arg), "%Y-%m-%d").ok()?;
+    Some(Period {
+        start: month,
+        end: month.checked_add_months(Months::new(1))?,
+        label: month.format("%Y-%m").to_string(),
+    })
+}
+
+/// 参数中指定的群号
+fn group_arg(spec: &CommandSpec, args: &[ArgValue]) -> Option<i64> {
+    spec.args
+        .iter()
+        .zip(args)
+        .find_map(|(arg_spec, value)| match (arg_spec.kind, value) {
+            (ArgKind::Group, ArgValue::Integer(group_id)) => Some(*group_id),
+            _ => None,
+        })
+}
+
+async fn dispatch(
+    config: &CoreConfig,
+    bot_help: Arc<BotHelp>,
+    invocation: Invocation,
+    scope: Scope,
+    user_id: i64,
+    group_id: Option<i64>,
+    sender_role: Option<&str>,
+) -> Result<Option<Vec<ApiPayload>>> {
+    let Some(command) = REGISTRY.find(&invocation.name, scope) else {
+        return Ok(None);
+    };
+    let spec = command.spec();
+    info!("Recv Command: {} {:?}", spec.name(), invocation.args);
+    let args = parse_args(spec, &invocation.args);
+    let role_group = group_id.or_else(|| args.as_ref().ok().and_then(|args| group_arg(spec, args)));
+    let role = role::user_role(&config.role, &bot_help, user_id, role_group, sender_role).await?;
+    let mut ctx = CommandContext {
+        config,
+        bot_help,
+        user_id,
+        role,
+        group_id,
+        reply_to: invocation.reply_to,
+        args: Vec::new(),
+    };
+    if spec.role > role {
+        warn!("Command {} Denied For: {}", spec.name(), user_id);
+        return match scope {
+            Scope::Private => Ok(None),
+            _ => ctx.reply_text(format!("非{}不可{}", spec.role, spec.name())),
+        };
+    }
+    match args {
+        Ok(args) => ctx.args = args,
+        Err(reply) => return ctx.reply_text(reply),
+    }
+    command.execute(ctx).await
+}
+
+/// 群聊消息是否调用了开始记录的指令
+pub fn starts_recording(config: &CoreConfig, message: &GroupMessage) -> bool {
+    group_invocation(message, config.command.prefix.as_deref())
+        .and_then(|invocation| REGISTRY.find(&invocation.name, Scope::Group))
+        .is_some_and(|command| command.records())
+}
+
+pub async fn dispatch_group(
+    config: &CoreConfig,
+    message: &GroupMessage,
+    bot_help: Arc<BotHelp>,
+) -> Result<Option<Vec<ApiPayload>>> {
+    let prefix = config.command.prefix.as_deref();
+    let Some(invocation) = group_invocation(message, prefix) else {
+        return Ok(None);
+    };
+    dispatch(
+        config,
+        bot_help,
+        invocation,
+        Scope::Group,
+        message.user_id,
+        Some(message.group_id),
+        message.sender.role.as_deref(),
+    )
+    .await
+}
+
+pub async fn dispatch_private(
+    config: &CoreConfig,
+    message: &PrivateMessage,
+    bot_help: Arc<BotHelp>,
+) -> Result<Option<Vec<ApiPayload>>> {
+    let prefix = config.command.prefix.as_deref();
+    let Some(invocation) = private_invocation(message, prefix) else {
+        return Ok(None);
+    };
+    dispatch(
+        config,
+        bot_help,
+        invocation,
+        Scope::Private,
+        message.user_id,
+        None,
+        None,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{at_bot, group_message, SELF_ID};
+    use onebot_v11::event::message::Message;
+
+    fn group(segments: Vec<MessageSegment>) -> GroupMessage {
+        match group_message(1, 2, segments) {
+            Message::GroupMessage(message) => message,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn group_invocation_requires_at_bot_or_prefix() {
+        assert_eq!(
+            group_invocation(&group(at_bot(" ls  2026-10 ")), None),
+            Some(Invocation {
+                name: "ls".to_string(),
+                args: vec!["2026-10".to_string()],
+                reply_to: None,
+            })
+        );
+        let mut replied = vec![MessageSegment::reply("42")];
+        replied.extend(at_bot("record"));
+        assert_eq!(
+            group_invocation(&group(replied), None).unwrap().reply_to,
+            Some("42".to_string())
+        );
+        let other = vec![
+            MessageSegment::at((SELF_ID + 1).to_string()),
+            MessageSegment::text("ls"),
+        ];
+        assert_eq!(group_invocation(&group(other), Some("/")), None);
+        let plain = group(vec![MessageSegment::text("ls")]);
+        assert_eq!(group_invocation(&plain, None), None);
+        assert_eq!(group_invocation(&plain, Some("/")), None);
+
+        let expected = Some(Invocation {
+            name: "ls".to_string(),
+            args: vec![],
+            reply_to: None,
+        });
+        let spaced = vec![
+            MessageSegment::text(" "),
+            MessageSegment::at(SELF_ID.to_string()),
+            MessageSegment::text(" ls"),
+        ];
+        assert_eq!(group_invocation(&group(spaced), None), expected);
+        let trailing_at = vec![
+            MessageSegment::text("ls "),
+            MessageSegment::at(SELF_ID.to_string()),
+        ];
+        assert_eq!(group_invocation(&group(trailing_at), None), expected);
+        let prefixed = group(vec![MessageSegment::text(" /ls")]);
+        assert_eq!(group_invocation(&prefixed, Some("/")), expected);
+        assert_eq!(group_invocation(&prefixed, Some("#")), None);
+    }
+
+    #[test]
+    fn periods_and_argument_errors() {
+        let spec = CommandSpec {
+            names: &["已记录", "ls"],
+            scope: Scope::Group,
+            role: Role::Viewer,
+            args: &[ArgSpec {
+                name: "日期",
+                kind: ArgKind::Period,
+                required: false,
+            }],
+            description: "列出记录",
+        };
+        let month = NaiveDate::from_ymd_opt(2026, 12, 1).unwrap();
+        assert_eq!(
+            parse_args(&spec, &["2026-12".to_string()]),
+            Ok(vec![ArgValue::Period(Period {
+                start: month,
+                end: NaiveDate::from_ymd_opt(2027, 1, 1).unwrap(),
+                label: "2026-12".to_string(),
+            })])
+        );
+        let day = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        assert_eq!(
+            parse_args(&spec, &["2026-10-18".to_string()]),
+            Ok(vec![ArgValue::Period(Period {
+                start: day,
+                end: day.succ_opt().unwrap(),
+                label: "2026-10-18".to_string(),
+            })])
+        );
+        assert_eq!(parse_args(&spec, &[]), Ok(vec![]));
+        assert_eq!(
+            parse_args(&spec, &["十月".to_string()]),
+            Err("参数格式错误：日期\n用法：已记录 [日期]".to_string())
+        );
+        assert_eq!(
+            parse_args(&spec, &["2026-10".to_string(), "x".to_string()]),
+            Err("参数过多\n用法：已记录 [日期]".to_string())
+        );
+    }
+}
@@ -1,46 +1,48 @@
 use crate::bot_help::BotHelp;
+use crate::config::{AttachmentConfig, CoreConfig};
+use crate::message_handle::command::CommandContext;
+use crate::session::SessionKey;
 use crate::status::BotStatus;
-use crate::utils::json_parse;
-use crate::utils::json_parse::JsonDataType;
+use crate::utils::attachment::{self, Attachment, AttachmentRejected};
+use crate::utils::card;
+use crate::utils::forward_message::{self, ForwardMessage, ForwardPart};
 use crate::utils::image;
 use crate::utils::reply_message;
 use anyhow::{Error, Result};
-use chrono::{Duration, Local, TimeZone};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
 use onebot_v11::api::payload::ApiPayload;
 use onebot_v11::api::payload::SendGroupMsg;
 use onebot_v11::event::message::GroupMessage;
 use onebot_v11::MessageSegment;
 use std::ops::Add;
+use std::path::Path;
 use std::sync::Arc;
-use onebot_v11::message::segment::{ImageData, JsonData, TextData};
+use onebot_v11::message::segment::{ForwardData, ImageData, JsonData, TextData};
 use tracing::{error, info, warn};
+use url::Url;
 
-static COMPLETE_CONTENT_RECORD_REPLY: &str = "内容记录完成，如果还需记录请回复：1\n当前记录者做为署名者请回复：2\n跳过署名请回复：3\n修改署名者请直接输入";
+static COMPLETE_CONTENT_RECORD_REPLY: &str = "内容记录完成，如果还需记录请回复：1\n当前记录者做为署名者请回复：2\n跳过署名请回复：3\n修改署名者请直接输入\n放弃本次记录请回复：取消";
+static CANCEL_KEYWORDS: [&str; 2] = ["取消", "cancel"];
 
-pub async fn handle_record_start(
-    message: GroupMessage,
-    bot_help: Arc<BotHelp>,
-) -> Result<Option<Vec<ApiPayload>>> {
-    bot_help.update_status(BotStatus::RecordTitle).await?;
-    bot_help.set_record_user_id(message.user_id).await?;
-    let group_id = message.group_id;
-    let at_message = MessageSegment::at(message.user_id.to_string());
-    let text_message = MessageSegment::text("已收到记录指令，请输入标题");
-    Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
-        group_id,
-        message: vec![at_message, text_message],
-        auto_escape: false,
-    })]))
+pub async fn handle_record_start(ctx: &CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+    ctx.bot_help
+        .sessions
+        .update_status(ctx.session_key()?, BotStatus::RecordTitle)
+        .await;
+    let at_message = MessageSegment::at(ctx.user_id.to_string());
+    let text_message = MessageSegment::text("已收到记录指令，请输入标题（回复“取消”可放弃记录）");
+    Ok(Some(vec![ctx.reply(vec![at_message, text_message])]))
 }
 
 pub async fn handle_record_title(
+    config: &CoreConfig,
     message: GroupMessage,
     bot_help: Arc<BotHelp>,
 ) -> Result<Option<Vec<ApiPayload>>> {
-    if !bot_help.check_record_user_id(message.user_id).await? {
-        info!("not recording user");
-        return Ok(None);
+    if is_cancel_message(&message) {
+        return handle_record_cancel(message, bot_help, "已取消本次记录").await;
     }
+    let session_key = SessionKey::from(&message);
     if message.message.len() == 1 {
         if let MessageSegment::Text { data } = message.message[0].clone() {
             if data.text.len() > bot_help.max_title_length().await? {
@@ -50,9 +52,33 @@ pub async fn handle_record_title(
                     auto_escape: false,
                 })]));
             }
-            let uuid = bot_help.insert_new_record(data.text).await?;
-            bot_help.set_recording_uuid(uuid).await?;
-            bot_help.update_status(BotStatus::RecordContent).await?;
+            let uuid = bot_help.insert_new_record(message.group_id, data.text).await?;
+            bot_help
+                .sessions
+                .set_recording_uuid(&session_key, uuid.clone())
+                .await?;
+            let pending_content = bot_help.sessions.take_pending_content(&session_key).await;
+            if !pending_content.is_empty() {
+                let mut reply_messages = vec![
+                    MessageSegment::at(message.user_id.to_string()),
+                    MessageSegment::text("标题记录成功\n"),
+                ];
+                handle_record_message_list_content(&config.attachment, &pending_content, &bot_help, &mut reply_messages, &uuid).await?;
+                bot_help
+                    .sessions
+                    .update_status(session_key, BotStatus::RecordRemark)
+                    .await;
+                reply_messages.push(MessageSegment::text(COMPLETE_CONTENT_RECORD_REPLY));
+                return Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
+                    group_id: message.group_id,
+                    message: reply_messages,
+                    auto_escape: false,
+                })]));
+            }
+            bot_help
+                .sessions
+                .update_status(session_key, BotStatus::RecordContent)
+                .await;
             let at_message = MessageSegment::at(message.user_id.to_string());
             let text_message = MessageSegment::text("标题记录成功，请输入内容");
             return Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
@@ -70,17 +96,21 @@ pub async fn handle_record_title(
 }
 
 pub async fn handle_record_content(
+    config: &CoreConfig,
     message: GroupMessage,
     bot_help: Arc<BotHelp>,
 ) -> Result<Option<Vec<ApiPayload>>> {
-    if !bot_help.check_record_user_id(message.user_id).await? {
-        info!("not recording user");
-        return Ok(None);
+    if is_cancel_message(&message) {
+        return handle_record_cancel(message, bot_help, "已取消本次记录").await;
     }
+    let session_key = SessionKey::from(&message);
     let mut reply_messages = Vec::<MessageSegment>::new();
-    let uuid = bot_help.recording_uuid().await?;
-    handle_record_message_list_content(&message, &bot_help, &mut reply_messages, &uuid).await?;
-    bot_help.update_status(BotStatus::RecordRemark).await?;
+    let uuid = bot_help.sessions.recording_uuid(&session_key).await?;
+    handle_record_message_list_content(&config.attachment, &message.message, &bot_help, &mut reply_messages, &uuid).await?;
+    bot_help
+        .sessions
+        .update_status(session_key, BotStatus::RecordRemark)
+        .await;
     reply_messages.push(MessageSegment::text(COMPLETE_CONTENT_RECORD_REPLY));
     Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
         group_id: message.group_id,
@@ -89,12 +119,31 @@ pub async fn handle_record_content(
     })]))
 }
 
-async fn handle_record_message_list_content(message: &GroupMessage, bot_help: &Arc<BotHelp>, reply_messages: &mut Vec<MessageSegment>, uuid: &String) -> Result<(), Error> {
-    for msg in message.message.iter() {
+async fn handle_record_message_list_content(config: &AttachmentConfig, segments: &[MessageSegment], bot_help: &Arc<BotHelp>, reply_messages: &mut Vec<MessageSegment>, uuid: &str) -> Result<(), Error> {
+    for msg in segments {
         match msg {
             MessageSegment::Text { data } => handle_text_content(bot_help, uuid, data.clone()).await?,
             MessageSegment::Image { data } => handle_image_content(bot_help, reply_messages, uuid, data.clone()).await?,
             MessageSegment::Json { data } => handle_json_content(bot_help, reply_messages, uuid, data).await?,
+            MessageSegment::Forward { data } => handle_forward_content(bot_help, reply_messages, uuid, data).await?,
+            MessageSegment::File { data } => {
+                let attachment = Attachment {
+                    name: data.name.clone().unwrap_or(data.file.clone()),
+                    url: data.url.clone(),
+                    file_id: Some(data.file_id.clone()).filter(|file_id| !file_id.is_empty()),
+                    size: data.file_size.parse().ok(),
+                };
+                handle_attachment_content(config, bot_help, reply_messages, uuid, attachment, "file").await?
+            }
+            MessageSegment::Video { data } => {
+                let attachment = Attachment {
+                    name: data.file.clone(),
+                    url: data.url.clone(),
+                    file_id: None,
+                    size: None,
+                };
+                handle_attachment_content(config, bot_help, reply_messages, uuid, attachment, "video").await?
+            }
             other => {
                 warn!("Unsupported message: {:?}", other);
                 reply_messages.push(MessageSegment::text("此内容暂不支持记录:\n"));
@@ -113,44 +162,159 @@ async fn handle_text_content(bot_help: &Arc<BotHelp>, uuid: &str, data: TextData
 }
 
 async fn handle_json_content(bot_help: &Arc<BotHelp>, reply_messages: &mut Vec<MessageSegment>, uuid: &str, data: &JsonData) -> Result<(), Error> {
-    match json_parse::check_json_data_type(&data.data)? {
-        JsonDataType::WeChatShare => {
-            let contents = json_parse::get_wechat_share_content(&data.data)?;
-            for content in contents {
+    let Some(card) = card::parse_card(&data.data) else {
+        warn!("Not Support Json Message: {:?}", data.data);
+        reply_messages.push(MessageSegment::text("内容解析失败，此内容暂不支持"));
+        return Ok(());
+    };
+    // 卡片标题已作为记录标题时不再重复记录
+    let record_title = bot_help.select_record(uuid).await?.map(|record| record.title);
+    let title = Some(card.title).filter(|title| Some(title) != record_title.as_ref());
+    for content in [title.unwrap_or_default(), card.description, card.url] {
+        if content.is_empty() {
+            continue;
+        }
+        bot_help
+            .record_content(uuid.to_string(), content, "text".to_string())
+            .await?;
+    }
+    if let Some(preview) = card.preview {
+        // 预览图获取失败不影响卡片本身的记录
+        match save_image(bot_help, uuid, preview.clone(), &preview_file_name(&preview)).await {
+            Ok((_, image_path)) => {
                 bot_help
-                    .record_content(uuid.to_string(), content, "text".to_string())
+                    .record_content(uuid.to_string(), image_path, "image".to_string())
                     .await?;
             }
+            Err(e) => warn!("Card Preview Get Error: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// 预览图链接常常没有扩展名，此时按 jpg 保存
+fn preview_file_name(url: &str) -> String {
+    let name = Url::parse(url)
+        .ok()
+        .and_then(|url| url.path_segments()?.next_back().map(str::to_string))
+        .unwrap_or_default();
+    match name.rsplit_once('.') {
+        Some((_, extension)) if (1..=4).contains(&extension.len()) && extension.chars().all(|c| c.is_ascii_alphanumeric()) => name,
+        _ => "preview.jpg".to_string(),
+    }
+}
+
+/// 记录的附件目录，相对于分享目录
+async fn record_asset_dir(bot_help: &Arc<BotHelp>, uuid: &str) -> Result<String> {
+    Ok(match bot_help.select_record(uuid).await? {
+        Some(record) => image::record_image_dir(&record),
+        None => format!("{}/{}", Local::now().format("%Y-%m"), uuid),
+    })
+}
+
+/// 下载图片到记录的图片目录，返回 (文件名, 相对于分享目录的路径)
+async fn save_image(bot_help: &Arc<BotHelp>, uuid: &str, url: String, file: &str) -> Result<(String, String)> {
+    let image_dir = record_asset_dir(bot_help, uuid).await?;
+    let image_save_path = format!("{}/{}", bot_help.share_path().await?, image_dir);
+    let image_type = file.split(".").last().unwrap();
+    let save_image_name = image::get_image(url, image_type.to_string(), image_save_path).await?;
+    let image_path = format!("{}/{}", image_dir, save_image_name);
+    Ok((save_image_name, image_path))
+}
+
+async fn handle_forward_content(bot_help: &Arc<BotHelp>, reply_messages: &mut Vec<MessageSegment>, uuid: &str, data: &ForwardData) -> Result<(), Error> {
+    let nodes = match forward_message::get_forward_nodes(&data.id, bot_help.clone()).await {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            error!("Forward Message Get Error: {}", e);
+            reply_messages.push(MessageSegment::text("合并转发消息获取失败"));
+            return Ok(());
+        }
+    };
+    let mut messages = Vec::new();
+    for node in nodes {
+        let mut parts: Vec<ForwardPart> = Vec::new();
+        for segment in node.message {
+            let text = match segment {
+                MessageSegment::Text { data } => data.text,
+                MessageSegment::Image { data } => match data.url {
+                    Some(url) => match save_image(bot_help, uuid, url, &data.file).await {
+                        Ok((_, path)) => {
+                            parts.push(ForwardPart::Image { path });
+                            continue;
+                        }
+                        Err(e) => {
+                            error!("Image Get Error: {}", e);
+                            "[图片获取失败]".to_string()
+                        }
+                    },
+                    None => "[图片]".to_string(),
+                },
+                MessageSegment::Forward { .. } => "[合并转发]".to_string(),
+                other => {
+                    warn!("Unsupported forward content: {:?}", other);
+                    continue;
+                }
+            };
+            // 相邻的文本合并为一段
+            match parts.last_mut() {
+                Some(ForwardPart::Text { text: last }) => last.push_str(&text),
+                _ => parts.push(ForwardPart::Text { text }),
+            }
         }
-        JsonDataType::Other => {
-            warn!("Not Support Json Message: {:?}", data.data);
-            reply_messages.push(MessageSegment::text("内容解析失败，此内容暂不支持"));
+        messages.push(ForwardMessage {
+            sender: node.sender,
+            parts,
+        });
+    }
+    bot_help
+        .record_content(uuid.to_string(), serde_json::to_string(&messages)?, "forward".to_string())
+        .await?;
+    reply_messages.push(MessageSegment::text(format!(
+        "合并转发记录成功: {} 条消息\n",
+        messages.len()
+    )));
+    Ok(())
+}
+
+/// 群文件与视频，`content_type` 为 `file` 或 `video`，内容为相对于分享目录的路径
+async fn handle_attachment_content(config: &AttachmentConfig, bot_help: &Arc<BotHelp>, reply_messages: &mut Vec<MessageSegment>, uuid: &str, attachment: Attachment, content_type: &str) -> Result<(), Error> {
+    let asset_dir = record_asset_dir(bot_help, uuid).await?;
+    let save_dir = Path::new(&bot_help.share_path().await?).join(&asset_dir);
+    match attachment::save_attachment(config, bot_help.clone(), &attachment, &save_dir).await {
+        Ok(save_name) => {
+            let kind = if content_type == "video" { "视频" } else { "文件" };
+            reply_messages.push(MessageSegment::text(format!("{}记录成功: {}\n", kind, save_name)));
+            bot_help
+                .record_content(uuid.to_string(), format!("{}/{}", asset_dir, save_name), content_type.to_string())
+                .await?;
+        }
+        Err(e) => {
+            error!("Attachment Get Error: {}", e);
+            let reply = match e.downcast_ref::<AttachmentRejected>() {
+                Some(rejected) => format!("{}\n", rejected),
+                None => format!("文件获取失败: {}\n", attachment.name),
+            };
+            reply_messages.push(MessageSegment::text(reply));
         }
     }
     Ok(())
 }
 
-async fn handle_image_content(bot_help: &Arc<BotHelp>, reply_messages: &mut Vec<MessageSegment>, uuid: &String, data: ImageData) -> Result<(), Error> {
+async fn handle_image_content(bot_help: &Arc<BotHelp>, reply_messages: &mut Vec<MessageSegment>, uuid: &str, data: ImageData) -> Result<(), Error> {
     match data.url {
         None => {
             reply_messages.push(MessageSegment::text("图片信息获取失败:\n"));
         }
         Some(url) => {
-            let image_save_path = format!(
-                "{}/{}/{}",
-                bot_help.share_path().await?,
-                Local::now().format("%Y-%m"),
-                uuid,
-            );
-            let image_type = data.file.split(".").last().unwrap();
-            match image::get_image(url, image_type.to_string(), image_save_path).await {
-                Ok(save_image_name) => {
+            match save_image(bot_help, uuid, url, &data.file).await {
+                Ok((save_image_name, image_path)) => {
                     reply_messages.push(MessageSegment::text(format!(
                         "图片记录成功: {}\n",
                         &save_image_name
                     )));
                     bot_help
-                        .record_content(uuid.clone(), save_image_name, "image".to_string())
+                        .record_content(uuid.to_string(), image_path, "image".to_string())
                         .await?;
                 },
                 Err(e) => {
@@ -167,15 +331,18 @@ pub async fn handle_record_remark(
     message: GroupMessage,
     bot_help: Arc<BotHelp>,
 ) -> Result<Option<Vec<ApiPayload>>> {
-    if !bot_help.check_record_user_id(message.user_id).await? {
-        info!("not recording user");
-        return Ok(None);
+    if is_cancel_message(&message) {
+        return handle_record_cancel(message, bot_help, "已取消本次记录").await;
     }
-    let uuid = bot_help.recording_uuid().await?;
+    let session_key = SessionKey::from(&message);
+    let uuid = bot_help.sessions.recording_uuid(&session_key).await?;
     if let MessageSegment::Text { data } = message.message[0].clone() {
         match data.text.as_str() {
             "1" => {
-                bot_help.update_status(BotStatus::RecordContent).await?;
+                bot_help
+                    .sessions
+                    .update_status(session_key, BotStatus::RecordContent)
+                    .await;
                 return Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
                     group_id: message.group_id,
                     message: vec![MessageSegment::text("请继续回复记录内容")],
@@ -202,7 +369,10 @@ pub async fn handle_record_remark(
             }
         }
     }
-    bot_help.update_status(BotStatus::WaitingCommand).await?;
+    bot_help
+        .sessions
+        .update_status(session_key, BotStatus::WaitingCommand)
+        .await;
     Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
         group_id: message.group_id,
         message: vec![MessageSegment::text("记录成功！")],
@@ -210,110 +380,116 @@ pub async fn handle_record_remark(
     })]))
 }
 
-pub async fn handle_record_select(
-    message: GroupMessage,
-    bot_help: Arc<BotHelp>,
-) -> Result<Option<Vec<ApiPayload>>> {
-    let now = Local::now();
-    let start_native = now
-        .date_naive()
-        .and_hms_opt(0, 0, 0)
-        .ok_or(Error::msg("start date get error"))?;
-    let end_native = (now.date_naive() + Duration::days(1))
-        .and_hms_opt(0, 0, 0)
-        .ok_or(Error::msg("end date get error"))?;
-    let start = Local
-        .from_local_datetime(&start_native)
-        .single()
-        .ok_or(Error::msg("single datetime get error"))?;
-    let end = Local
-        .from_local_datetime(&end_native)
-        .single()
-        .ok_or(Error::msg("single datetime get error"))?;
-    let records = bot_help.select_records_by_date(start, end).await?;
-    let mut reply_text = String::from("今日已记录：\n");
-    for record in records {
-        reply_text = reply_text.add(record.title.as_str()).add("\n");
+fn is_cancel_message(message: &GroupMessage) -> bool {
+    if let [MessageSegment::Text { data }] = message.message.as_slice() {
+        return CANCEL_KEYWORDS.contains(&data.text.trim());
     }
-    Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
-        group_id: message.group_id,
-        message: vec![MessageSegment::text(reply_text)],
-        auto_escape: false,
-    })]))
+    false
 }
 
-pub async fn handle_record_undo(
+/// 结束记录会话并丢弃未完成的记录
+pub async fn handle_record_cancel(
     message: GroupMessage,
     bot_help: Arc<BotHelp>,
+    reply: &str,
 ) -> Result<Option<Vec<ApiPayload>>> {
-    let admin_id = bot_help.bot_admin().await?;
-    if admin_id != message.user_id {
-        warn!("Group Message Sender Error: {:?}", message.sender);
-        return Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
-            group_id: message.group_id,
-            message: vec![MessageSegment::text("非管理员不可撤销")],
-            auto_escape: false,
-        })]));
+    let session_key = SessionKey::from(&message);
+    if let Ok(uuid) = bot_help.sessions.recording_uuid(&session_key).await {
+        info!("Cancel Recording: {}", uuid);
+        bot_help.delete_record(&uuid).await?;
+        bot_help.delete_content(&uuid).await?;
     }
-    let records = bot_help.select_all_records().await?;
-    let last_record = records.last().ok_or(Error::msg("last record"))?;
-    let uuid = last_record.id.clone();
-    bot_help.delete_record(&uuid).await?;
-    bot_help.delete_content(&uuid).await?;
+    bot_help
+        .sessions
+        .update_status(session_key, BotStatus::WaitingCommand)
+        .await;
     Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
         group_id: message.group_id,
-        message: vec![MessageSegment::text(format!(
-            "{}\n已删除",
-            last_record.title
-        ))],
+        message: vec![
+            MessageSegment::at(message.user_id.to_string()),
+            MessageSegment::text(reply),
+        ],
         auto_escape: false,
     })]))
 }
 
+/// 列出指定日期或月份的记录，默认为今日
+pub async fn handle_record_select(ctx: &CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+    let today = Local::now().date_naive();
+    let (start, end, label) = match ctx.period(0) {
+        Some(period) => (period.start, period.end, period.label.clone()),
+        None => (today, today + Duration::days(1), "今日".to_string()),
+    };
+    let records = ctx
+        .bot_help
+        .select_records_by_date(local_midnight(start)?, local_midnight(end)?)
+        .await?;
+    let mut reply_text = format!("{}已记录：\n", label);
+    for record in records {
+        reply_text = reply_text.add(record.title.as_str()).add("\n");
+    }
+    ctx.reply_text(reply_text)
+}
+
+fn local_midnight(date: NaiveDate) -> Result<DateTime<Local>> {
+    let native = date
+        .and_hms_opt(0, 0, 0)
+        .ok_or(Error::msg("date get error"))?;
+    Local
+        .from_local_datetime(&native)
+        .single()
+        .ok_or(Error::msg("single datetime get error"))
+}
+
+/// 删除本群最近的一条记录，群内身份只能管理本群的记录
+pub async fn handle_record_undo(ctx: &CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+    let group_id = ctx.group_id.ok_or(Error::msg("undo without group"))?;
+    let Some(last_record) = ctx.bot_help.select_last_record(group_id).await? else {
+        return ctx.reply_text("本群没有可以撤销的记录");
+    };
+    ctx.bot_help.delete_record(&last_record.id).await?;
+    ctx.bot_help.delete_content(&last_record.id).await?;
+    ctx.reply_text(format!("{}\n已删除", last_record.title))
+}
+
+/// 回复一条消息发起记录：被回复的消息（包括卡片）在输入标题后作为记录内容
 pub async fn handle_reply_record(
-    record_user: i64,
-    group_id: i64,
+    session_key: SessionKey,
     message_id: String,
     bot_help: Arc<BotHelp>,
 ) -> Result<Option<Vec<ApiPayload>>> {
     let original_message =
         reply_message::get_reply_original_message(message_id, bot_help.clone()).await?;
-    if original_message.message.len() == 1 {
-        match original_message.message[0].clone() {
-            MessageSegment::Text { data } => {
-                bot_help.set_tmp_content(data.text).await?;
-            }
-            MessageSegment::Json { data } => match json_parse::check_json_data_type(&data.data)? {
-                JsonDataType::WeChatShare => {
-                    let contents = json_parse::get_wechat_share_content(&data.data)?;
-                    let uuid = bot_help.insert_new_record(contents[0].clone()).await?;
-                    bot_help.set_record_user_id(record_user).await?;
-                    bot_help.set_recording_uuid(uuid.clone()).await?;
-                    bot_help
-                        .record_content(uuid.clone(), contents[1].clone(), "text".to_string())
-                        .await?;
-                    bot_help.set_recording_uuid(uuid).await?;
-                    bot_help.update_status(BotStatus::RecordRemark).await?;
-                    return Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
-                        group_id,
-                        message: vec![MessageSegment::text(COMPLETE_CONTENT_RECORD_REPLY)],
-                        auto_escape: false,
-                    })]))
-                }
-                JsonDataType::Other => {
-                    warn!("Not Support Json Message: {:?}", data.data);
-                    return Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
-                        group_id,
-                        message: vec![MessageSegment::text("此内容暂不支持解析")],
-                        auto_escape: false,
-                    })]));
-                }
-            },
-            other => {
-                warn!("Unexpected message segment: {:?}", other);
-                return Ok(None);
-            }
-        }
-    }
-    Ok(None)
+    // 被回复消息中的回复和 @ 不是内容，原样记录还会在回执中再次 @ 对方
+    let pending_content: Vec<MessageSegment> = original_message
+        .message
+        .into_iter()
+        .filter(|segment| {
+            !matches!(
+                segment,
+                MessageSegment::Reply { .. } | MessageSegment::At { .. }
+            )
+        })
+        .collect();
+    let notice = if pending_content.is_empty() {
+        "已收到记录指令，请输入标题（回复“取消”可放弃记录）"
+    } else {
+        "已收到记录指令，被回复的消息将作为记录内容，请输入标题（回复“取消”可放弃记录）"
+    };
+    bot_help
+        .sessions
+        .update_status(session_key, BotStatus::RecordTitle)
+        .await;
+    bot_help
+        .sessions
+        .set_pending_content(&session_key, pending_content)
+        .await?;
+    Ok(Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
+        group_id: session_key.group_id,
+        message: vec![
+            MessageSegment::at(session_key.user_id.to_string()),
+            MessageSegment::text(notice),
+        ],
+        auto_escape: false,
+    })]))
 }
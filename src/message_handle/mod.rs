@@ -4,17 +4,47 @@ use anyhow::Result;
 use onebot_v11::api::payload::ApiPayload;
 use onebot_v11::event::message::Message;
 use crate::config::CoreConfig;
+use crate::session::SessionKey;
+use tracing::info;
 
+mod command;
 mod group;
 mod private;
+#[cfg(test)]
+mod tests;
 
 pub async fn handle_message(
     config: &CoreConfig,
     message: Message,
     bot_help: Arc<BotHelp>,
 ) -> Result<Option<Vec<ApiPayload>>> {
+    let (group_id, user_id) = match &message {
+        Message::PrivateMessage(msg) => (None, msg.user_id),
+        Message::GroupMessage(msg) => (Some(msg.group_id), msg.user_id),
+    };
+    // 处理期间超时检查不会取走该会话
+    let _handling = match &message {
+        Message::GroupMessage(msg) => Some(bot_help.sessions.begin_handling(SessionKey::from(msg))),
+        Message::PrivateMessage(_) => None,
+    };
+    // 所有者不受名单限制
+    if user_id != bot_help.bot_admin().await? {
+        if config.access.denied_users.contains(&user_id) {
+            info!("Ignore Message From: {} In {:?}", user_id, group_id);
+            return Ok(None);
+        }
+        if let Message::GroupMessage(msg) = &message {
+            if let Some(refusal) = config.access.recording_refusal(msg.group_id, user_id) {
+                if group::is_recording(config, msg, &bot_help).await {
+                    info!("Refuse Recording From: {} In {}", user_id, msg.group_id);
+                    // 记录会话中不再允许记录时放弃未完成的记录
+                    return group::record::handle_record_cancel(msg.clone(), bot_help, refusal).await;
+                }
+            }
+        }
+    }
     match message {
         Message::PrivateMessage(msg) => private::handle_private_message(config, msg, bot_help).await,
-        Message::GroupMessage(msg) => group::handle_group_message(msg, bot_help).await,
+        Message::GroupMessage(msg) => group::handle_group_message(config, msg, bot_help).await,
     }
 }
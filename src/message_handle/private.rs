@@ -1,12 +1,12 @@
 use std::sync::Arc;
 use crate::config::CoreConfig;
 use crate::bot_help::BotHelp;
-use crate::status::BotStatus;
+use crate::message_handle::command::{self, CommandContext};
+use crate::role::Role;
 use anyhow::Result;
 use onebot_v11::api::payload::ApiPayload;
 use onebot_v11::event::message::PrivateMessage;
-use onebot_v11::MessageSegment;
-use tracing::{debug, info, warn};
+use tracing::{debug, info};
 
 pub async fn handle_private_message(
     config: &CoreConfig,
@@ -19,21 +19,99 @@ pub async fn handle_private_message(
         info!("Message: {:?}", msg);
     }
     debug!("Private Message: {:?}", message);
-    if message.message.len() == 1 {
-        if let MessageSegment::Text { data } = message.message[0].clone() {
-            let admin_id = bot_help.bot_admin().await?;
-            if admin_id != message.user_id {
-                warn!("Private Message Sender Error: {:?}", message.sender);
-                return Ok(None);
-            }
-            match data.text.as_str() {
-                "git" => return crate::utils::git::auto_git_task(&config.git, admin_id).await,
-                "reset" => {
-                    bot_help.update_status(BotStatus::WaitingCommand).await?;
-                }
-                _ => {}
-            }
+    command::dispatch_private(config, &message, bot_help).await
+}
+
+pub async fn handle_git(ctx: &CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+    let _publish = ctx.bot_help.publish_lock.lock().await;
+    crate::utils::git::auto_git_task(&ctx.config.git, ctx.user_id, &ctx.bot_help).await
+}
+
+pub async fn handle_repair_images(ctx: &CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+    let report = crate::utils::image::repair_image_paths(&ctx.bot_help).await?;
+    ctx.reply_text(format!(
+        "图片路径修复完成，更新 {} 张，缺失 {} 张",
+        report.moved, report.missing
+    ))
+}
+
+/// 群号为空时为全局身份；非所有者只能授予、修改低于自己的身份
+pub async fn handle_grant(ctx: &CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+    let (Some(user_id), Some(role)) = (ctx.integer(0), ctx.role(1)) else {
+        return Ok(None);
+    };
+    let group_id = ctx.integer(2);
+    if ctx.role < Role::Owner && role >= ctx.role {
+        return ctx.reply_text(format!("只能授予低于{}的身份", ctx.role));
+    }
+    if let Some(current) = assigned_role(ctx, user_id, group_id).await? {
+        if ctx.role < Role::Owner && current >= ctx.role {
+            return ctx.reply_text(format!("不能修改{}的身份：{}", user_id, current));
+        }
+    }
+    ctx.bot_help.set_user_role(user_id, group_id, role).await?;
+    info!("Grant {} To {} In {:?}", role, user_id, group_id);
+    ctx.reply_text(format!(
+        "已将 {} 设为{}（{}）",
+        user_id,
+        role,
+        role_scope(group_id)
+    ))
+}
+
+pub async fn handle_revoke(ctx: &CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+    let Some(user_id) = ctx.integer(0) else {
+        return Ok(None);
+    };
+    let group_id = ctx.integer(1);
+    let scope = role_scope(group_id);
+    match assigned_role(ctx, user_id, group_id).await? {
+        None => ctx.reply_text(format!("{} 没有分配身份（{}）", user_id, scope)),
+        Some(current) if ctx.role < Role::Owner && current >= ctx.role => {
+            ctx.reply_text(format!("不能修改{}的身份：{}", user_id, current))
         }
+        Some(_) => {
+            ctx.bot_help.remove_user_role(user_id, group_id).await?;
+            info!("Revoke Role Of {} In {:?}", user_id, group_id);
+            ctx.reply_text(format!("已取消 {} 的身份（{}）", user_id, scope))
+        }
+    }
+}
+
+pub async fn handle_list_roles(ctx: &CommandContext<'_>) -> Result<Option<Vec<ApiPayload>>> {
+    let roles = ctx.bot_help.all_user_roles().await?;
+    if roles.is_empty() {
+        return ctx.reply_text("暂无分配的身份");
+    }
+    let mut reply = String::from("身份列表：");
+    for (user_id, group_id, role) in roles {
+        reply.push_str(&format!(
+            "\n{}：{}（{}）",
+            user_id,
+            role,
+            role_scope(group_id)
+        ));
+    }
+    ctx.reply_text(reply)
+}
+
+async fn assigned_role(
+    ctx: &CommandContext<'_>,
+    user_id: i64,
+    group_id: Option<i64>,
+) -> Result<Option<Role>> {
+    Ok(ctx
+        .bot_help
+        .user_roles(user_id)
+        .await?
+        .into_iter()
+        .find(|(assigned_group, _)| *assigned_group == group_id)
+        .map(|(_, role)| role))
+}
+
+fn role_scope(group_id: Option<i64>) -> String {
+    match group_id {
+        Some(group_id) => format!("群 {}", group_id),
+        None => "全局".to_string(),
     }
-    Ok(None)
 }
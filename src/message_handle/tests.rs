@@ -0,0 +1,790 @@
+use crate::config::{AccessConfig, GroupUsersConfig};
+use crate::role::Role;
+use crate::test_utils::*;
+use chrono::Local;
+use onebot_v11::api::payload::{ApiPayload, SendGroupMsg};
+use onebot_v11::message::segment::{File, ImageData, JsonData, VideoData};
+use onebot_v11::MessageSegment;
+use serde_json::json;
+use std::fs;
+
+const GROUP_A: i64 = 20001;
+const GROUP_B: i64 = 20002;
+const USER_A: i64 = 30001;
+const USER_B: i64 = 30002;
+
+const COMPLETE_CONTENT_RECORD_REPLY: &str = "内容记录完成，如果还需记录请回复：1\n当前记录者做为署名者请回复：2\n跳过署名请回复：3\n修改署名者请直接输入\n放弃本次记录请回复：取消";
+
+fn image(file: &str, url: String) -> MessageSegment {
+    MessageSegment::Image {
+        data: ImageData {
+            file: file.to_string(),
+            r#type: None,
+            summary: None,
+            url: Some(url),
+            cache: None,
+            proxy: None,
+            timeout: None,
+        },
+    }
+}
+
+fn reply_texts(payloads: Option<Vec<ApiPayload>>) -> Vec<String> {
+    let mut texts = Vec::new();
+    for payload in payloads.unwrap_or_default() {
+        let message = match payload {
+            ApiPayload::SendGroupMsg(SendGroupMsg { message, .. }) => message,
+            ApiPayload::SendPrivateMsg(msg) => msg.message,
+            other => panic!("unexpected payload: {:?}", other),
+        };
+        for segment in message {
+            if let MessageSegment::Text { data } = segment {
+                texts.push(data.text);
+            }
+        }
+    }
+    texts
+}
+
+async fn record(bot: &TestBot, group_id: i64, user_id: i64, title: &str, content: &str) {
+    bot.send_group(group_id, user_id, at_bot("record")).await.unwrap();
+    bot.send_group(group_id, user_id, text(title)).await.unwrap();
+    bot.send_group(group_id, user_id, text(content)).await.unwrap();
+    bot.send_group(group_id, user_id, text("3")).await.unwrap();
+}
+
+#[tokio::test]
+async fn record_then_generate_writes_pages() {
+    let bot = TestBot::new().await;
+    let http = MockHttpServer::start().await;
+    http.route("GET", "/cat.png", 200, "image/png", b"png-bytes");
+
+    let reply = bot.send_group(GROUP_A, USER_A, at_bot("record")).await.unwrap();
+    assert_eq!(
+        reply,
+        group_reply(
+            GROUP_A,
+            vec![
+                MessageSegment::at(USER_A.to_string()),
+                MessageSegment::text("已收到记录指令，请输入标题（回复“取消”可放弃记录）"),
+            ]
+        )
+    );
+
+    let reply = bot.send_group(GROUP_A, USER_A, text("Rust 周报")).await.unwrap();
+    assert_eq!(
+        reply,
+        group_reply(
+            GROUP_A,
+            vec![
+                MessageSegment::at(USER_A.to_string()),
+                MessageSegment::text("标题记录成功，请输入内容"),
+            ]
+        )
+    );
+
+    let content = vec![
+        MessageSegment::text("https://example.com/weekly"),
+        image("cat.png", http.url("/cat.png")),
+    ];
+    let texts = reply_texts(bot.send_group(GROUP_A, USER_A, content).await.unwrap());
+    assert_eq!(texts.len(), 2);
+    let image_name = texts[0]
+        .strip_prefix("图片记录成功: ")
+        .unwrap()
+        .trim_end()
+        .to_string();
+    assert!(image_name.ends_with(".png"));
+    let requests = http.requests();
+    assert_eq!((requests[0].method.as_str(), requests[0].path.as_str()), ("GET", "/cat.png"));
+    assert_eq!(texts[1], COMPLETE_CONTENT_RECORD_REPLY);
+
+    let reply = bot.send_group(GROUP_A, USER_A, text("2")).await.unwrap();
+    assert_eq!(
+        reply,
+        group_reply(GROUP_A, vec![MessageSegment::text("记录成功！")])
+    );
+
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot("generate")).await.unwrap();
+    assert_eq!(
+        reply,
+        group_reply(
+            GROUP_A,
+            vec![MessageSegment::text("记录文件生成成功，更新了 3 个文件")]
+        )
+    );
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot("generate")).await.unwrap();
+    assert_eq!(
+        reply,
+        group_reply(GROUP_A, vec![MessageSegment::text("记录文件没有变化")])
+    );
+
+    let records = bot.bot_help.select_all_records().await.unwrap();
+    assert_eq!(records.len(), 1);
+    let uuid = &records[0].id;
+    let now = Local::now();
+    let month = now.format("%Y-%m").to_string();
+    let day = now.format("%Y-%m-%d").to_string();
+    let share_path = bot.share_path();
+
+    assert_eq!(
+        fs::read(share_path.join(&month).join(uuid).join(&image_name)).unwrap(),
+        b"png-bytes"
+    );
+    let contents = bot.bot_help.select_all_content().await.unwrap();
+    assert_eq!(contents[1].content, format!("{}/{}/{}", month, uuid, image_name));
+    assert_eq!(
+        fs::read_to_string(share_path.join("README.md")).unwrap(),
+        format!(
+            "---\ntitle: 分享\nicon: comments\nindex: false\n---\n\n\n\n- [{month}]({month}/README.md)\n"
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(share_path.join(&month).join("README.md")).unwrap(),
+        format!(
+            "---\ntitle: {month}月分享整理\nicon: circle-info\nindex: false\n---\n\n\n\n- [{day}]({day}.md)\n"
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(share_path.join(&month).join(format!("{day}.md"))).unwrap(),
+        format!(
+            "---\ntitle: {}日分享整理\nicon: circle-info\n---\n\n## Rust 周报\n\n（分享者：user{USER_A}）\n\nhttps://example.com/weekly\n\n![image]({uuid}/{image_name})\n\n",
+            now.format("%Y年%m月%d")
+        )
+    );
+}
+
+#[tokio::test]
+async fn sessions_in_different_groups_do_not_block_each_other() {
+    let bot = TestBot::new().await;
+
+    bot.send_group(GROUP_A, USER_A, at_bot("record")).await.unwrap();
+    bot.send_group(GROUP_B, USER_B, at_bot("record")).await.unwrap();
+    bot.send_group(GROUP_B, USER_B, text("B 的标题")).await.unwrap();
+    bot.send_group(GROUP_A, USER_A, text("A 的标题")).await.unwrap();
+
+    // 其他群成员不受记录会话影响，普通聊天不会被当成标题
+    let reply = bot.send_group(GROUP_A, USER_B, text("随便聊聊")).await.unwrap();
+    assert_eq!(reply, None);
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot("ls")).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["今日已记录：\nB 的标题\nA 的标题\n".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn cancel_discards_half_built_record() {
+    let bot = TestBot::new().await;
+
+    bot.send_group(GROUP_A, USER_A, at_bot("record")).await.unwrap();
+    bot.send_group(GROUP_A, USER_A, text("要取消的记录")).await.unwrap();
+    let reply = bot.send_group(GROUP_A, USER_A, text("取消")).await.unwrap();
+    assert_eq!(
+        reply,
+        group_reply(
+            GROUP_A,
+            vec![
+                MessageSegment::at(USER_A.to_string()),
+                MessageSegment::text("已取消本次记录"),
+            ]
+        )
+    );
+    assert!(bot.bot_help.select_all_records().await.unwrap().is_empty());
+    assert_eq!(bot.send_group(GROUP_A, USER_A, text("之后的消息")).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn list_accepts_month_or_day_argument() {
+    let bot = TestBot::new().await;
+    record(&bot, GROUP_A, USER_A, "本月的记录", "内容").await;
+    let now = Local::now();
+    let month = now.format("%Y-%m").to_string();
+
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot("ls")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["今日已记录：\n本月的记录\n".to_string()]);
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot(&format!("已记录 {month}"))).await.unwrap();
+    assert_eq!(reply_texts(reply), vec![format!("{month}已记录：\n本月的记录\n")]);
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot("list 2000-01-01")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["2000-01-01已记录：\n".to_string()]);
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot("ls 十月")).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["参数格式错误：日期\n用法：已记录 [日期]".to_string()]
+    );
+    assert_eq!(bot.send_private(USER_B, text("ls")).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn help_lists_commands_available_to_the_caller() {
+    let bot = TestBot::new().await;
+    let member_help = "可用指令：\n记录（record、rc）：开始记录，回复一条消息时以该消息为记录内容\n生成（generate、gen）：生成记录文件\n已记录（list、ls）：列出某天或某月的记录，默认为今日\n帮助（help）：查看可用指令，或某个指令的详细用法\n发送“帮助 <指令>”查看详细用法";
+    let reply = bot.send_group(GROUP_A, USER_A, at_bot("帮助")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec![member_help.to_string()]);
+
+    let texts = reply_texts(bot.send_group(GROUP_A, ADMIN_ID, at_bot("help")).await.unwrap());
+    assert!(texts[0].contains("\n撤销（undo）：删除本群最近的一条记录\n"));
+    assert!(!texts[0].contains("git"));
+
+    let texts = reply_texts(bot.send_private(USER_A, text("help")).await.unwrap());
+    assert_eq!(
+        texts,
+        vec!["可用指令：\n帮助（help）：查看可用指令，或某个指令的详细用法\n发送“帮助 <指令>”查看详细用法".to_string()]
+    );
+    let texts = reply_texts(bot.send_private(ADMIN_ID, text("help")).await.unwrap());
+    assert!(texts[0].contains("\ngit：提交并推送记录文件\nreset：清空所有进行中的记录\n"));
+
+    let reply = bot.send_group(GROUP_A, USER_A, at_bot("help ls")).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["用法：已记录 [日期]\n别名：list、ls\n说明：列出某天或某月的记录，默认为今日\n日期：YYYY-MM 或 YYYY-MM-DD（可选）".to_string()]
+    );
+    let reply = bot.send_group(GROUP_A, USER_A, at_bot("help undo")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["没有找到指令：undo".to_string()]);
+}
+
+#[tokio::test]
+async fn configured_prefix_triggers_commands_without_mention() {
+    let mut bot = TestBot::new().await;
+    assert_eq!(bot.send_group(GROUP_A, USER_A, text("#生成")).await.unwrap(), None);
+
+    bot.config.command.prefix = Some("#".to_string());
+    let reply = bot.send_group(GROUP_A, USER_A, text("#生成")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["记录文件生成成功，更新了 1 个文件".to_string()]);
+    let reply = bot.send_group(GROUP_A, USER_A, at_bot("生成")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["记录文件没有变化".to_string()]);
+    let reply = bot.send_group(GROUP_A, USER_A, text("#记录")).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["已收到记录指令，请输入标题（回复“取消”可放弃记录）".to_string()]
+    );
+    let reply = bot.send_private(ADMIN_ID, text("#reset")).await.unwrap();
+    assert_eq!(reply, None);
+    let reply = bot.send_group(GROUP_A, USER_A, text("#生成")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["记录文件没有变化".to_string()]);
+}
+
+#[tokio::test]
+async fn undo_is_limited_to_admin() {
+    let bot = TestBot::new().await;
+    record(&bot, GROUP_A, USER_A, "第一条", "内容").await;
+
+    let reply = bot.send_group(GROUP_A, USER_A, at_bot("undo")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["非管理员不可撤销".to_string()]);
+    assert_eq!(bot.bot_help.select_all_records().await.unwrap().len(), 1);
+
+    let reply = bot.send_group(GROUP_A, ADMIN_ID, at_bot("undo")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["第一条\n已删除".to_string()]);
+    assert!(bot.bot_help.select_all_records().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn undo_only_deletes_records_from_own_group() {
+    let bot = TestBot::new().await;
+    bot.bot_help
+        .set_user_role(USER_B, Some(GROUP_A), Role::Admin)
+        .await
+        .unwrap();
+    record(&bot, GROUP_A, USER_A, "本群记录", "内容").await;
+    record(&bot, GROUP_B, USER_A, "他群记录", "内容").await;
+
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot("undo")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["本群记录\n已删除".to_string()]);
+    let reply = bot.send_group(GROUP_A, USER_B, at_bot("undo")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["本群没有可以撤销的记录".to_string()]);
+    let reply = bot.send_group(GROUP_B, USER_B, at_bot("undo")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["非管理员不可撤销".to_string()]);
+
+    let records = bot.bot_help.select_all_records().await.unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].title, "他群记录");
+}
+
+#[tokio::test]
+async fn access_lists_only_limit_recording() {
+    let mut bot = TestBot::new().await;
+    bot.config.access = AccessConfig {
+        groups: vec![GROUP_A],
+        group_users: vec![GroupUsersConfig {
+            group_id: GROUP_A,
+            users: vec![USER_A],
+        }],
+        denied_users: vec![USER_B, ADMIN_ID],
+    };
+    let start_reply = vec!["已收到记录指令，请输入标题（回复“取消”可放弃记录）".to_string()];
+    let reply = bot.send_group(GROUP_A, USER_A, at_bot("记录")).await.unwrap();
+    assert_eq!(reply_texts(reply), start_reply);
+    bot.send_group(GROUP_A, USER_A, text("取消")).await.unwrap();
+    let reply = bot.send_group(GROUP_B, USER_A, at_bot("已记录")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["今日已记录：\n".to_string()]);
+    let reply = bot.send_group(GROUP_B, USER_A, at_bot("记录")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["本群未开启记录".to_string()]);
+    let reply = bot.send_group(GROUP_A, 30003, at_bot("记录")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["你没有在本群记录的权限".to_string()]);
+    let reply = bot.send_group(GROUP_B, ADMIN_ID, at_bot("记录")).await.unwrap();
+    assert_eq!(reply_texts(reply), start_reply);
+    bot.send_group(GROUP_B, ADMIN_ID, text("取消")).await.unwrap();
+    assert!(bot.send_private(ADMIN_ID, text("help")).await.unwrap().is_some());
+
+    assert_eq!(bot.send_group(GROUP_A, USER_B, at_bot("已记录")).await.unwrap(), None);
+    assert_eq!(bot.send_private(USER_B, text("help")).await.unwrap(), None);
+
+    bot.send_group(GROUP_A, USER_A, at_bot("记录")).await.unwrap();
+    bot.config.access.groups = vec![GROUP_B];
+    let reply = bot.send_group(GROUP_A, USER_A, text("标题")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["本群未开启记录".to_string()]);
+    assert_eq!(bot.send_group(GROUP_A, USER_A, text("标题")).await.unwrap(), None);
+    assert!(bot.bot_help.select_all_records().await.unwrap().is_empty());
+
+    bot.config.access.groups.clear();
+    assert_eq!(bot.send_group(GROUP_B, USER_B, at_bot("记录")).await.unwrap(), None);
+    let reply = bot.send_group(GROUP_B, USER_A, at_bot("记录")).await.unwrap();
+    assert_eq!(reply_texts(reply), start_reply);
+}
+
+#[tokio::test]
+async fn group_admin_manages_roles_of_own_group_in_private() {
+    let bot = TestBot::new().await;
+    bot.bot_help
+        .set_user_role(USER_B, Some(GROUP_A), Role::Admin)
+        .await
+        .unwrap();
+    let texts = reply_texts(bot.send_private(USER_B, text("help")).await.unwrap());
+    assert!(texts[0].contains("\n授权（grant）：设置用户在某个群或全局的身份（需指定你有权限的群号）\n"));
+    assert!(!texts[0].contains("git"));
+    let reply = bot.send_private(USER_B, text("help grant")).await.unwrap();
+    assert!(reply_texts(reply)[0].starts_with("用法：授权 <QQ号> <身份> [群号]"));
+
+    let reply = bot
+        .send_private(USER_B, text(&format!("grant {} viewer {}", USER_A, GROUP_A)))
+        .await
+        .unwrap();
+    assert_eq!(reply_texts(reply), vec![format!("已将 {} 设为查看者（群 {}）", USER_A, GROUP_A)]);
+    let grant_other_group = format!("grant {} viewer {}", USER_A, GROUP_B);
+    assert_eq!(bot.send_private(USER_B, text(&grant_other_group)).await.unwrap(), None);
+    let grant_global = format!("grant {} viewer", USER_A);
+    assert_eq!(bot.send_private(USER_B, text(&grant_global)).await.unwrap(), None);
+    let revoke_other_group = format!("revoke {} {}", USER_A, GROUP_B);
+    assert_eq!(bot.send_private(USER_B, text(&revoke_other_group)).await.unwrap(), None);
+    let reply = bot
+        .send_private(USER_B, text(&format!("revoke {} {}", USER_A, GROUP_A)))
+        .await
+        .unwrap();
+    assert_eq!(reply_texts(reply), vec![format!("已取消 {} 的身份（群 {}）", USER_A, GROUP_A)]);
+    assert!(bot.bot_help.user_roles(USER_A).await.unwrap().is_empty());
+
+    let reply = bot
+        .send_private(ADMIN_ID, text(&format!("grant {} viewer 0", USER_A)))
+        .await
+        .unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["参数格式错误：群号\n用法：授权 <QQ号> <身份> [群号]".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn roles_are_granted_per_group_and_globally() {
+    let bot = TestBot::new().await;
+    let reply = bot
+        .send_private(ADMIN_ID, text(&format!("授权 {} viewer {}", USER_A, GROUP_A)))
+        .await
+        .unwrap();
+    assert_eq!(reply_texts(reply), vec![format!("已将 {} 设为查看者（群 {}）", USER_A, GROUP_A)]);
+    let reply = bot.send_group(GROUP_A, USER_A, at_bot("记录")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["非记录者不可记录".to_string()]);
+    let reply = bot.send_group(GROUP_B, USER_A, at_bot("记录")).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["已收到记录指令，请输入标题（回复“取消”可放弃记录）".to_string()]
+    );
+
+    let reply = bot
+        .send_private(ADMIN_ID, text(&format!("grant {} 管理员", USER_B)))
+        .await
+        .unwrap();
+    assert_eq!(reply_texts(reply), vec![format!("已将 {} 设为管理员（全局）", USER_B)]);
+    let reply = bot
+        .send_private(USER_B, text(&format!("grant {} admin", USER_A)))
+        .await
+        .unwrap();
+    assert_eq!(reply_texts(reply), vec!["只能授予低于管理员的身份".to_string()]);
+    let reply = bot
+        .send_private(USER_B, text(&format!("revoke {} {}", USER_A, GROUP_A)))
+        .await
+        .unwrap();
+    assert_eq!(reply_texts(reply), vec![format!("已取消 {} 的身份（群 {}）", USER_A, GROUP_A)]);
+    let reply = bot.send_private(USER_B, text("grant abc viewer")).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["参数格式错误：QQ号\n用法：授权 <QQ号> <身份> [群号]".to_string()]
+    );
+
+    let reply = bot.send_private(ADMIN_ID, text("roles")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec![format!("身份列表：\n{}：管理员（全局）", USER_B)]);
+    assert_eq!(bot.send_private(USER_A, text("roles")).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn reply_record_fetches_original_message() {
+    let bot = TestBot::new().await;
+    let http = MockHttpServer::start().await;
+    http.route("GET", "/preview.png", 200, "image/png", b"preview-bytes");
+    let wechat_share = json!({
+        "app": "com.tencent.structmsg",
+        "bizsrc": "",
+        "config": {"ctime": 1, "forward": 1, "token": "token", "type": "normal"},
+        "extra": {"app_type": 1, "appid": 1, "msg_seq": 1, "uin": 1},
+        "meta": {"news": {
+            "app_type": 1,
+            "appid": 1,
+            "ctime": 1,
+            "desc": "desc",
+            "jumpUrl": "https://mp.weixin.qq.com/s?__biz=BIZ&mid=1&idx=2&sn=SN&chksm=x#rd",
+            "preview": http.url("/preview.png"),
+            "tag": "公众号",
+            "tagIcon": "",
+            "title": "一篇文章",
+            "uin": 1
+        }},
+        "prompt": "[分享]一篇文章",
+        "ver": "0.0.0.1",
+        "view": "news"
+    });
+    bot.onebot.set_response(
+        "get_msg",
+        json!({
+            "time": 0,
+            "message_type": "group",
+            "message_id": 42,
+            "real_id": 42,
+            "sender": {"user_id": USER_B},
+            "message": [{"type": "json", "data": {"data": wechat_share.to_string()}}],
+        }),
+    );
+
+    let message = vec![
+        MessageSegment::reply("42"),
+        MessageSegment::at(SELF_ID.to_string()),
+        MessageSegment::text("record"),
+    ];
+    let reply = bot.send_group(GROUP_A, USER_A, message).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["已收到记录指令，被回复的消息将作为记录内容，请输入标题（回复“取消”可放弃记录）".to_string()]
+    );
+    assert_eq!(bot.onebot.api_calls(), vec![("get_msg".to_string(), json!({"message_id": 42}))]);
+    assert!(bot.bot_help.select_all_records().await.unwrap().is_empty());
+
+    let reply = bot.send_group(GROUP_A, USER_A, text("一篇文章")).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["标题记录成功\n".to_string(), COMPLETE_CONTENT_RECORD_REPLY.to_string()]
+    );
+    bot.send_group(GROUP_A, USER_A, text("3")).await.unwrap();
+
+    let records = bot.bot_help.select_all_records().await.unwrap();
+    assert_eq!(records[0].title, "一篇文章");
+    let contents = bot.bot_help.select_all_content().await.unwrap();
+    assert!(contents.iter().all(|content| content.uuid == records[0].id));
+    let texts: Vec<_> = contents
+        .iter()
+        .take(2)
+        .map(|content| content.content.as_str())
+        .collect();
+    assert_eq!(texts, vec!["desc", "https://mp.weixin.qq.com/s?__biz=BIZ&mid=1&idx=2&sn=SN"]);
+    assert_eq!(contents.len(), 3);
+    assert_eq!(contents[2].content_type, "image");
+    assert_eq!(fs::read(bot.share_path().join(&contents[2].content)).unwrap(), b"preview-bytes");
+}
+
+#[tokio::test]
+async fn forward_record_renders_quoted_conversation() {
+    let bot = TestBot::new().await;
+    let http = MockHttpServer::start().await;
+    http.route("GET", "/chat.png", 200, "image/png", b"chat-bytes");
+    bot.onebot.set_response(
+        "get_forward_msg",
+        json!({"message": [
+            {"type": "node", "data": {"id": "101"}},
+            {"type": "node", "data": {"id": "102"}},
+        ]}),
+    );
+    bot.onebot.set_response_for(
+        "get_msg",
+        json!({"message_id": 101}),
+        json!({
+            "time": 0,
+            "message_type": "group",
+            "message_id": 101,
+            "real_id": 101,
+            "sender": {"user_id": USER_A, "nickname": "zhangsan", "card": "张三"},
+            "message": [
+                {"type": "text", "data": {"text": "你好\n第二行"}},
+                {"type": "image", "data": {"file": "chat.png", "url": http.url("/chat.png")}},
+            ],
+        }),
+    );
+    bot.onebot.set_response_for(
+        "get_msg",
+        json!({"message_id": 102}),
+        json!({
+            "time": 0,
+            "message_type": "group",
+            "message_id": 102,
+            "real_id": 102,
+            "sender": {"user_id": USER_B, "nickname": "李四"},
+            "message": [{"type": "text", "data": {"text": "收到"}}],
+        }),
+    );
+
+    bot.send_group(GROUP_A, USER_A, at_bot("record")).await.unwrap();
+    bot.send_group(GROUP_A, USER_A, text("群聊讨论")).await.unwrap();
+    let reply = bot
+        .send_group(GROUP_A, USER_A, vec![MessageSegment::forward("fw1")])
+        .await
+        .unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec![
+            "合并转发记录成功: 2 条消息\n".to_string(),
+            COMPLETE_CONTENT_RECORD_REPLY.to_string()
+        ]
+    );
+    bot.send_group(GROUP_A, USER_A, text("3")).await.unwrap();
+    bot.send_group(GROUP_A, USER_A, at_bot("generate")).await.unwrap();
+
+    let records = bot.bot_help.select_all_records().await.unwrap();
+    let uuid = &records[0].id;
+    let contents = bot.bot_help.select_all_content().await.unwrap();
+    assert_eq!(contents[0].content_type, "forward");
+    let now = Local::now();
+    let month = now.format("%Y-%m").to_string();
+    let day = now.format("%Y-%m-%d").to_string();
+    let page = fs::read_to_string(bot.share_path().join(&month).join(format!("{day}.md"))).unwrap();
+    let image_link = page
+        .lines()
+        .find_map(|line| line.strip_prefix("> ![image]("))
+        .unwrap()
+        .trim_end_matches(')')
+        .to_string();
+    assert!(image_link.starts_with(&format!("{uuid}/")));
+    assert_eq!(
+        fs::read(bot.share_path().join(&month).join(&image_link)).unwrap(),
+        b"chat-bytes"
+    );
+    assert!(page.ends_with(&format!(
+        "## 群聊讨论\n\n> **张三**\n>\n> 你好\n> 第二行\n>\n> ![image]({image_link})\n\n> **李四**\n>\n> 收到\n\n"
+    )));
+}
+
+#[tokio::test]
+async fn file_and_video_are_saved_as_attachments() {
+    let bot = TestBot::new().await;
+    let http = MockHttpServer::start().await;
+    http.route("GET", "/clip.mp4", 200, "video/mp4", b"mp4-bytes");
+    bot.onebot.set_response(
+        "get_file",
+        json!({"file": "", "file_name": "周报.pdf", "file_size": 9, "base64": "cGRmLWJ5dGVz"}),
+    );
+    let file = |name: &str, file_id: &str| MessageSegment::File {
+        data: File {
+            file: name.to_string(),
+            name: None,
+            path: String::new(),
+            url: None,
+            file_id: file_id.to_string(),
+            file_size: "9".to_string(),
+        },
+    };
+    let video = MessageSegment::Video {
+        data: VideoData {
+            file: "clip.mp4".to_string(),
+            url: Some(http.url("/clip.mp4")),
+            cache: None,
+            proxy: None,
+            timeout: None,
+        },
+    };
+
+    bot.send_group(GROUP_A, USER_A, at_bot("record")).await.unwrap();
+    bot.send_group(GROUP_A, USER_A, text("附件")).await.unwrap();
+    let content = vec![file("周报.pdf", "f1"), file("setup.exe", "f2"), video];
+    let reply = bot.send_group(GROUP_A, USER_A, content).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec![
+            "文件记录成功: 周报.pdf\n".to_string(),
+            "不支持记录此类型的文件: setup.exe\n".to_string(),
+            "视频记录成功: clip.mp4\n".to_string(),
+            COMPLETE_CONTENT_RECORD_REPLY.to_string(),
+        ]
+    );
+    assert_eq!(bot.onebot.api_calls(), vec![("get_file".to_string(), json!({"file_id": "f1"}))]);
+    bot.send_group(GROUP_A, USER_A, text("3")).await.unwrap();
+    bot.send_group(GROUP_A, USER_A, at_bot("generate")).await.unwrap();
+
+    let uuid = &bot.bot_help.select_all_records().await.unwrap()[0].id;
+    let now = Local::now();
+    let month = now.format("%Y-%m").to_string();
+    let day = now.format("%Y-%m-%d").to_string();
+    let record_dir = bot.share_path().join(&month).join(uuid);
+    assert_eq!(fs::read(record_dir.join("周报.pdf")).unwrap(), b"pdf-bytes");
+    assert_eq!(fs::read(record_dir.join("clip.mp4")).unwrap(), b"mp4-bytes");
+    let contents = bot.bot_help.select_all_content().await.unwrap();
+    let types: Vec<_> = contents.iter().map(|content| content.content_type.as_str()).collect();
+    assert_eq!(types, vec!["file", "video"]);
+    let page = fs::read_to_string(bot.share_path().join(&month).join(format!("{day}.md"))).unwrap();
+    assert!(page.ends_with(&format!(
+        "## 附件\n\n[周报.pdf]({uuid}/%E5%91%A8%E6%8A%A5.pdf)\n\n<video src=\"{uuid}/clip.mp4\" controls></video>\n\n"
+    )));
+}
+
+#[tokio::test]
+async fn card_content_records_link_and_preview() {
+    let bot = TestBot::new().await;
+    let http = MockHttpServer::start().await;
+    http.route("GET", "/cover", 200, "image/jpeg", b"cover-bytes");
+    let mini_app = json!({
+        "app": "com.tencent.miniapp_01",
+        "meta": {"detail_1": {
+            "title": "哔哩哔哩",
+            "desc": "一个视频",
+            "preview": http.url("/cover"),
+            "qqdocurl": "https://b23.tv/AbCdEfG?share_medium=android&share_source=qq",
+        }},
+        "prompt": "[QQ小程序]一个视频",
+    });
+    let card = MessageSegment::Json {
+        data: JsonData {
+            data: mini_app.to_string(),
+        },
+    };
+
+    bot.send_group(GROUP_A, USER_A, at_bot("record")).await.unwrap();
+    bot.send_group(GROUP_A, USER_A, text("视频分享")).await.unwrap();
+    let reply = bot.send_group(GROUP_A, USER_A, vec![card]).await.unwrap();
+    assert_eq!(reply_texts(reply), vec![COMPLETE_CONTENT_RECORD_REPLY.to_string()]);
+
+    let contents: Vec<_> = bot
+        .bot_help
+        .select_all_content()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|content| (content.content_type, content.content))
+        .collect();
+    assert_eq!(contents.len(), 4);
+    assert_eq!(
+        contents[..3],
+        [
+            ("text".to_string(), "一个视频".to_string()),
+            ("text".to_string(), "哔哩哔哩".to_string()),
+            ("text".to_string(), "https://b23.tv/AbCdEfG".to_string()),
+        ]
+    );
+    assert_eq!(contents[3].0, "image");
+    assert!(contents[3].1.ends_with(".jpg"));
+    assert_eq!(fs::read(bot.share_path().join(&contents[3].1)).unwrap(), b"cover-bytes");
+}
+
+#[tokio::test]
+async fn reply_record_prefills_any_message_as_content() {
+    let bot = TestBot::new().await;
+    let http = MockHttpServer::start().await;
+    http.route("GET", "/photo.png", 200, "image/png", b"photo-bytes");
+    bot.onebot.set_response(
+        "get_msg",
+        json!({
+            "time": 0,
+            "message_type": "group",
+            "message_id": 43,
+            "real_id": 43,
+            "sender": {"user_id": USER_B},
+            "message": [
+                {"type": "reply", "data": {"id": "7"}},
+                {"type": "at", "data": {"qq": USER_A.to_string()}},
+                {"type": "text", "data": {"text": "看看这个"}},
+                {"type": "image", "data": {"file": "photo.png", "url": http.url("/photo.png")}},
+            ],
+        }),
+    );
+
+    let message = vec![
+        MessageSegment::reply("43"),
+        MessageSegment::at(SELF_ID.to_string()),
+        MessageSegment::text("record"),
+    ];
+    let reply = bot.send_group(GROUP_A, USER_A, message).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["已收到记录指令，被回复的消息将作为记录内容，请输入标题（回复“取消”可放弃记录）".to_string()]
+    );
+    assert!(bot.bot_help.select_all_records().await.unwrap().is_empty());
+
+    let texts = reply_texts(bot.send_group(GROUP_A, USER_A, text("一张图")).await.unwrap());
+    assert_eq!(texts.len(), 3);
+    assert_eq!(texts[0], "标题记录成功\n");
+    assert!(texts[1].starts_with("图片记录成功: "));
+    assert_eq!(texts[2], COMPLETE_CONTENT_RECORD_REPLY);
+    bot.send_group(GROUP_A, USER_A, text("3")).await.unwrap();
+
+    let records = bot.bot_help.select_all_records().await.unwrap();
+    assert_eq!(records[0].title, "一张图");
+    let contents = bot.bot_help.select_all_content().await.unwrap();
+    assert_eq!(contents.len(), 2);
+    assert_eq!(contents[0].content, "看看这个");
+    assert_eq!(contents[1].content_type, "image");
+    assert_eq!(fs::read(bot.share_path().join(&contents[1].content)).unwrap(), b"photo-bytes");
+}
+
+#[tokio::test]
+async fn private_reset_clears_sessions_for_admin_only() {
+    let bot = TestBot::new().await;
+    bot.send_group(GROUP_A, USER_A, at_bot("record")).await.unwrap();
+
+    bot.send_private(USER_A, text("reset")).await.unwrap();
+    let reply = bot.send_group(GROUP_A, USER_A, text("仍是标题")).await.unwrap();
+    assert_eq!(reply_texts(reply), vec!["标题记录成功，请输入内容".to_string()]);
+
+    bot.send_private(ADMIN_ID, text("reset")).await.unwrap();
+    assert_eq!(bot.send_group(GROUP_A, USER_A, text("内容")).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn repair_images_moves_legacy_files_into_record_month() {
+    let bot = TestBot::new().await;
+    let uuid = bot.bot_help.insert_new_record(GROUP_A, "旧记录".to_string()).await.unwrap();
+    let share_path = bot.share_path();
+    let legacy_dir = share_path.join("2020-01").join(&uuid);
+    fs::create_dir_all(&legacy_dir).unwrap();
+    fs::write(legacy_dir.join("old.png"), b"old").unwrap();
+    for file in ["old.png", "lost.png"] {
+        bot.bot_help
+            .record_content(uuid.clone(), file.to_string(), "image".to_string())
+            .await
+            .unwrap();
+    }
+
+    let reply = bot.send_private(USER_A, text("repair-images")).await.unwrap();
+    assert_eq!(reply, None);
+    let reply = bot.send_private(ADMIN_ID, text("repair-images")).await.unwrap();
+    assert_eq!(
+        reply_texts(reply),
+        vec!["图片路径修复完成，更新 1 张，缺失 1 张".to_string()]
+    );
+
+    let month = Local::now().format("%Y-%m").to_string();
+    let moved = format!("{}/{}/old.png", month, uuid);
+    assert_eq!(fs::read(share_path.join(&moved)).unwrap(), b"old");
+    assert!(!legacy_dir.join("old.png").exists());
+    let contents: Vec<String> = bot
+        .bot_help
+        .select_all_content()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|content| content.content)
+        .collect();
+    assert_eq!(contents, vec![moved, "lost.png".to_string()]);
+}
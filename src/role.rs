@@ -0,0 +1,155 @@
+use crate::bot_help::BotHelp;
+use crate::config::RoleConfig;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// 用户身份，按权限从低到高排列
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    /// 只能查看记录
+    Viewer,
+    /// 可以记录和生成
+    Recorder,
+    /// 可以撤销记录、推送以及管理低于自己的身份
+    Admin,
+    /// 配置中的 `admin` 始终是所有者
+    Owner,
+}
+
+impl Role {
+    /// 保存到数据库中的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Recorder => "recorder",
+            Role::Admin => "admin",
+            Role::Owner => "owner",
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Role::Viewer => "查看者",
+            Role::Recorder => "记录者",
+            Role::Admin => "管理员",
+            Role::Owner => "所有者",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "viewer" | "查看者" => Ok(Role::Viewer),
+            "recorder" | "记录者" => Ok(Role::Recorder),
+            "admin" | "管理员" => Ok(Role::Admin),
+            "owner" | "所有者" => Ok(Role::Owner),
+            _ => Err(anyhow!("unknown role: {}", value)),
+        }
+    }
+}
+
+/// 用户在群聊（`group_id` 为 `Some`）或私聊中的身份：
+/// 群内身份优先于全局身份，都没有时使用默认身份；群主、群管理员按配置提升身份
+pub async fn user_role(
+    config: &RoleConfig,
+    bot_help: &BotHelp,
+    user_id: i64,
+    group_id: Option<i64>,
+    sender_role: Option<&str>,
+) -> Result<Role> {
+    if bot_help.bot_admin().await? == user_id {
+        return Ok(Role::Owner);
+    }
+    let assigned = bot_help.user_roles(user_id).await?;
+    let group_role = group_id.and_then(|group_id| {
+        assigned
+            .iter()
+            .find(|(assigned_group, _)| *assigned_group == Some(group_id))
+    });
+    let global_role = assigned
+        .iter()
+        .find(|(assigned_group, _)| assigned_group.is_none());
+    let role = group_role
+        .or(global_role)
+        .map(|(_, role)| *role)
+        .unwrap_or(config.default_role);
+    let mapped = match (group_id, sender_role) {
+        (Some(_), Some("owner")) => config.group_owner,
+        (Some(_), Some("admin")) => config.group_admin,
+        _ => None,
+    };
+    Ok(mapped.map_or(role, |mapped| mapped.max(role)))
+}
+
+/// 用户在各个群中被分配的最高身份
+pub async fn highest_group_role(bot_help: &BotHelp, user_id: i64) -> Result<Option<Role>> {
+    Ok(bot_help
+        .user_roles(user_id)
+        .await?
+        .into_iter()
+        .filter(|(group_id, _)| group_id.is_some())
+        .map(|(_, role)| role)
+        .max())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{TestBot, ADMIN_ID};
+
+    #[tokio::test]
+    async fn group_role_overrides_global_and_default() {
+        let bot = TestBot::new().await;
+        let mut config = RoleConfig {
+            group_admin: Some(Role::Admin),
+            ..RoleConfig::default()
+        };
+        let role = |config: &RoleConfig, user_id, group_id, sender_role| {
+            let config = config.clone();
+            let bot_help = bot.bot_help.clone();
+            async move {
+                user_role(&config, &bot_help, user_id, group_id, sender_role)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert_eq!(role(&config, ADMIN_ID, None, None).await, Role::Owner);
+        assert_eq!(role(&config, 1, Some(100), None).await, Role::Recorder);
+        config.default_role = Role::Viewer;
+        assert_eq!(role(&config, 1, Some(100), None).await, Role::Viewer);
+
+        bot.bot_help
+            .set_user_role(1, None, Role::Admin)
+            .await
+            .unwrap();
+        bot.bot_help
+            .set_user_role(1, Some(100), Role::Viewer)
+            .await
+            .unwrap();
+        assert_eq!(role(&config, 1, None, None).await, Role::Admin);
+        assert_eq!(role(&config, 1, Some(100), None).await, Role::Viewer);
+        assert_eq!(role(&config, 1, Some(200), None).await, Role::Admin);
+
+        assert_eq!(
+            role(&config, 1, Some(100), Some("admin")).await,
+            Role::Admin
+        );
+        assert_eq!(
+            role(&config, 1, Some(100), Some("owner")).await,
+            Role::Viewer
+        );
+        assert_eq!(role(&config, 1, None, Some("admin")).await, Role::Admin);
+        assert_eq!("记录者".parse::<Role>().unwrap(), Role::Recorder);
+        assert!("root".parse::<Role>().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::bot_help::BotHelp;
+use crate::config::{GenerateConfig, GitConfig, ScheduleConfig};
+use crate::generator;
+use crate::utils::git;
+use anyhow::Result;
+use chrono::Local;
+use cron::Schedule;
+use onebot_v11::api::payload::{ApiPayload, SendPrivateMsg};
+use onebot_v11::MessageSegment;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// 按 cron 表达式定时执行 `generate` 与 `git`，结果私聊发送给管理员
+pub fn start_publish_task(
+    schedule: ScheduleConfig,
+    generate: GenerateConfig,
+    git: GitConfig,
+    bot_help: Arc<BotHelp>,
+) -> Result<()> {
+    if !schedule.enable {
+        return Ok(());
+    }
+    let cron = Schedule::from_str(&schedule.cron)?;
+    info!("Scheduled Publish: {}", schedule.cron);
+    tokio::spawn(async move {
+        for next in cron.upcoming_owned(Local) {
+            let wait = (next - Local::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+            let payloads = match publish(&schedule, &generate, &git, &bot_help).await {
+                Ok(payloads) => payloads,
+                Err(e) => {
+                    error!("Scheduled Publish Error: {:?}", e);
+                    match bot_help.bot_admin().await {
+                        Ok(admin_id) => {
+                            vec![private_text(admin_id, format!("定时发布失败：{}", e))]
+                        }
+                        Err(e) => {
+                            error!("Get Bot Admin Error: {:?}", e);
+                            continue;
+                        }
+                    }
+                }
+            };
+            for payload in payloads {
+                if let Err(e) = bot_help.ws_connect.clone().call_api(payload).await {
+                    error!("Send Scheduled Publish Result Error: {:?}", e);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn publish(
+    schedule: &ScheduleConfig,
+    generate: &GenerateConfig,
+    git: &GitConfig,
+    bot_help: &BotHelp,
+) -> Result<Vec<ApiPayload>> {
+    let admin_id = bot_help.bot_admin().await?;
+    let _publish = bot_help.publish_lock.lock().await;
+    let records = bot_help.select_all_records().await?;
+    let written = generator::generate_by_records(generate, bot_help, records).await?;
+    if schedule.skip_without_new_records && written == 0 {
+        info!("Scheduled Publish Skipped: nothing generated");
+        return Ok(Vec::new());
+    }
+    let mut payloads = vec![private_text(
+        admin_id,
+        format!("定时发布：更新了 {} 个文件", written),
+    )];
+    payloads.extend(
+        git::auto_git_task(git, admin_id, bot_help)
+            .await?
+            .unwrap_or_default(),
+    );
+    Ok(payloads)
+}
+
+fn private_text(user_id: i64, text: String) -> ApiPayload {
+    ApiPayload::SendPrivateMsg(SendPrivateMsg {
+        user_id,
+        message: vec![MessageSegment::text(text)],
+        auto_escape: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GitCredential, PublishMode};
+    use crate::test_utils::TestBot;
+    use git2::{Repository, Signature, Time};
+    use std::fs;
+    use std::path::Path;
+
+    fn texts(payloads: &[ApiPayload]) -> Vec<String> {
+        payloads
+            .iter()
+            .flat_map(|payload| match payload {
+                ApiPayload::SendPrivateMsg(SendPrivateMsg { message, .. }) => message.clone(),
+                other => panic!("unexpected payload: {:?}", other),
+            })
+            .filter_map(|segment| match segment {
+                MessageSegment::Text { data } => Some(data.text),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 分享目录本身作为文档仓库，已有一条很早之前的提交
+    fn init_repository(share_path: &Path, remote_path: &Path) -> GitConfig {
+        Repository::init_bare(remote_path).unwrap();
+        let repo = Repository::init(share_path).unwrap();
+        fs::write(share_path.join(".keep"), "").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(".keep")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::new("bot", "bot@example.com", &Time::new(0, 0)).unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        GitConfig {
+            repository_dir: share_path.to_string_lossy().to_string(),
+            url: remote_path.to_string_lossy().to_string(),
+            username: "bot".to_string(),
+            user_email: "bot@example.com".to_string(),
+            credential: GitCredential::None,
+            publish: PublishMode::Push,
+            disable_owner_validation: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn publish_skips_when_nothing_was_generated() {
+        let bot = TestBot::new().await;
+        let remote_dir = tempfile::tempdir().unwrap();
+        let git = init_repository(&bot.share_path(), remote_dir.path());
+        let schedule = ScheduleConfig {
+            enable: true,
+            ..ScheduleConfig::default()
+        };
+        let generate = GenerateConfig::default();
+        let uuid = bot
+            .bot_help
+            .insert_new_record(0, "定时发布".to_string())
+            .await
+            .unwrap();
+
+        let payloads = publish(&schedule, &generate, &git, &bot.bot_help)
+            .await
+            .unwrap();
+        let published = texts(&payloads);
+        assert_eq!(published[0], "定时发布：更新了 3 个文件");
+        assert!(published[1].starts_with("git任务完成\n提交："));
+        assert!(
+            published[1].ends_with("新增文件：6，修改文件：0，删除文件：0\n新增记录：1，删除记录：0")
+        );
+
+        let payloads = publish(&schedule, &generate, &git, &bot.bot_help)
+            .await
+            .unwrap();
+        assert!(payloads.is_empty());
+
+        bot.bot_help.delete_record(&uuid).await.unwrap();
+        let payloads = publish(&schedule, &generate, &git, &bot.bot_help)
+            .await
+            .unwrap();
+        assert!(texts(&payloads)[1].ends_with("\n新增记录：0，删除记录：1"));
+    }
+
+    #[test]
+    fn invalid_cron_is_rejected() {
+        let schedule = ScheduleConfig {
+            enable: true,
+            cron: "every day".to_string(),
+            ..ScheduleConfig::default()
+        };
+        let bot_help = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(TestBot::new())
+            .bot_help;
+        assert!(start_publish_task(
+            schedule,
+            GenerateConfig::default(),
+            GitConfig::default(),
+            bot_help
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use crate::bot_help::BotHelp;
+use crate::config::{RecordSessionConfig, TimeoutAction};
+use crate::status::BotStatus;
+use crate::utils::image;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local};
+use onebot_v11::api::payload::{ApiPayload, SendGroupMsg};
+use onebot_v11::event::message::GroupMessage;
+use onebot_v11::MessageSegment;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SessionKey {
+    pub group_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordSession {
+    pub status: BotStatus,
+    pub recording_uuid: Option<String>,
+    /// 回复消息发起记录时被回复的消息，输入标题后作为记录内容
+    pub pending_content: Vec<MessageSegment>,
+    pub last_active: DateTime<Local>,
+}
+
+/// 记录会话，按 (群号, 用户) 区分，不存在的会话视为 `WaitingCommand`
+#[derive(Default)]
+pub struct SessionStore {
+    sessions: Mutex<HashMap<SessionKey, RecordSession>>,
+    /// 正在处理消息的会话及处理数，超时检查会跳过这些会话
+    handling: Arc<std::sync::Mutex<HashMap<SessionKey, usize>>>,
+}
+
+/// 会话的消息处理标记，释放时结束处理
+pub struct HandlingGuard {
+    handling: Arc<std::sync::Mutex<HashMap<SessionKey, usize>>>,
+    key: SessionKey,
+}
+
+impl Drop for HandlingGuard {
+    fn drop(&mut self) {
+        let mut handling = self.handling.lock().unwrap();
+        if let Some(count) = handling.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                handling.remove(&self.key);
+            }
+        }
+    }
+}
+
+impl From<&GroupMessage> for SessionKey {
+    fn from(message: &GroupMessage) -> Self {
+        SessionKey {
+            group_id: message.group_id,
+            user_id: message.user_id,
+        }
+    }
+}
+
+impl SessionStore {
+    /// 标记会话正在处理消息，需在读取会话状态前调用
+    pub fn begin_handling(&self, key: SessionKey) -> HandlingGuard {
+        *self.handling.lock().unwrap().entry(key).or_insert(0) += 1;
+        HandlingGuard {
+            handling: self.handling.clone(),
+            key,
+        }
+    }
+
+    pub async fn status(&self, key: &SessionKey) -> BotStatus {
+        self.sessions
+            .lock()
+            .await
+            .get(key)
+            .map(|session| session.status)
+            .unwrap_or(BotStatus::WaitingCommand)
+    }
+
+    pub async fn update_status(&self, key: SessionKey, status: BotStatus) {
+        let mut sessions = self.sessions.lock().await;
+        if let BotStatus::WaitingCommand = status {
+            sessions.remove(&key);
+            return;
+        }
+        sessions
+            .entry(key)
+            .and_modify(|session| {
+                session.status = status;
+                session.last_active = Local::now();
+            })
+            .or_insert(RecordSession {
+                status,
+                recording_uuid: None,
+                pending_content: Vec::new(),
+                last_active: Local::now(),
+            });
+    }
+
+    pub async fn recording_uuid(&self, key: &SessionKey) -> Result<String> {
+        self.sessions
+            .lock()
+            .await
+            .get(key)
+            .and_then(|session| session.recording_uuid.clone())
+            .ok_or(anyhow!("no recording uuid for {:?}", key))
+    }
+
+    pub async fn set_recording_uuid(&self, key: &SessionKey, uuid: String) -> Result<()> {
+        let mut sessions = self.sessions.lock().await;
+        let session = sessions
+            .get_mut(key)
+            .ok_or(anyhow!("no recording session for {:?}", key))?;
+        session.recording_uuid = Some(uuid);
+        session.last_active = Local::now();
+        Ok(())
+    }
+
+    pub async fn set_pending_content(
+        &self,
+        key: &SessionKey,
+        content: Vec<MessageSegment>,
+    ) -> Result<()> {
+        let mut sessions = self.sessions.lock().await;
+        let session = sessions
+            .get_mut(key)
+            .ok_or(anyhow!("no recording session for {:?}", key))?;
+        session.pending_content = content;
+        session.last_active = Local::now();
+        Ok(())
+    }
+
+    /// 取出待记录的内容，没有时返回空列表
+    pub async fn take_pending_content(&self, key: &SessionKey) -> Vec<MessageSegment> {
+        self.sessions
+            .lock()
+            .await
+            .get_mut(key)
+            .map(|session| std::mem::take(&mut session.pending_content))
+            .unwrap_or_default()
+    }
+
+    pub async fn clear(&self) {
+        self.sessions.lock().await.clear();
+    }
+
+    /// 取出所有超过 `idle_timeout` 未活动且没有在处理消息的会话
+    pub async fn take_expired(&self, idle_timeout: Duration) -> Vec<(SessionKey, RecordSession)> {
+        let deadline = Local::now() - idle_timeout;
+        let mut sessions = self.sessions.lock().await;
+        let handling = self.handling.lock().unwrap();
+        let expired_keys: Vec<SessionKey> = sessions
+            .iter()
+            .filter(|(key, session)| session.last_active < deadline && !handling.contains_key(key))
+            .map(|(key, _)| *key)
+            .collect();
+        expired_keys
+            .into_iter()
+            .filter_map(|key| sessions.remove(&key).map(|session| (key, session)))
+            .collect()
+    }
+}
+
+pub fn start_expire_task(config: RecordSessionConfig, bot_help: Arc<BotHelp>) {
+    if !config.enable {
+        return;
+    }
+    // interval 不接受 0，至少每秒检查一次
+    let check_interval = std::time::Duration::from_secs(config.check_interval.max(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(check_interval);
+        loop {
+            interval.tick().await;
+            match expire_sessions(&config, &bot_help).await {
+                Ok(payloads) => {
+                    for payload in payloads {
+                        if let Err(e) = bot_help.ws_connect.clone().call_api(payload).await {
+                            error!("Send Session Expire Notice Error: {:?}", e);
+                        }
+                    }
+                }
+                Err(e) => error!("Expire Record Session Error: {:?}", e),
+            }
+        }
+    });
+}
+
+async fn expire_sessions(
+    config: &RecordSessionConfig,
+    bot_help: &Arc<BotHelp>,
+) -> Result<Vec<ApiPayload>> {
+    let idle_timeout = Duration::seconds(config.idle_timeout as i64);
+    let mut payloads = Vec::new();
+    for (key, session) in bot_help.sessions.take_expired(idle_timeout).await {
+        info!("Record Session Expired: {:?}", key);
+        let notice = match (&config.timeout_action, session.recording_uuid) {
+            (TimeoutAction::Rollback, Some(uuid)) => {
+                let record = bot_help.select_record(&uuid).await?;
+                bot_help.delete_record(&uuid).await?;
+                bot_help.delete_content(&uuid).await?;
+                // 已下载的图片与附件一并删除
+                if let Some(record) = record {
+                    let asset_dir = Path::new(&bot_help.share_path().await?)
+                        .join(image::record_image_dir(&record));
+                    if asset_dir.exists() {
+                        std::fs::remove_dir_all(asset_dir)?;
+                    }
+                }
+                "记录超时，已撤销本次记录"
+            }
+            (TimeoutAction::Finalize, Some(_)) => "记录超时，已保存已记录的内容",
+            (_, None) => "记录超时，已取消本次记录",
+        };
+        payloads.push(ApiPayload::SendGroupMsg(SendGroupMsg {
+            group_id: key.group_id,
+            message: vec![
+                MessageSegment::at(key.user_id.to_string()),
+                MessageSegment::text(notice),
+            ],
+            auto_escape: false,
+        }));
+    }
+    Ok(payloads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestBot;
+
+    async fn start_expired_session(bot: &TestBot, user_id: i64) -> String {
+        let key = SessionKey {
+            group_id: 20001,
+            user_id,
+        };
+        let sessions = &bot.bot_help.sessions;
+        sessions.update_status(key, BotStatus::RecordContent).await;
+        let uuid = bot
+            .bot_help
+            .insert_new_record(key.group_id, "标题".to_string())
+            .await
+            .unwrap();
+        sessions
+            .set_recording_uuid(&key, uuid.clone())
+            .await
+            .unwrap();
+        sessions
+            .sessions
+            .lock()
+            .await
+            .get_mut(&key)
+            .unwrap()
+            .last_active = Local::now() - Duration::hours(1);
+        uuid
+    }
+
+    fn notice(payloads: &[ApiPayload]) -> String {
+        match payloads {
+            [ApiPayload::SendGroupMsg(msg)] => match &msg.message[1] {
+                MessageSegment::Text { data } => data.text.clone(),
+                _ => unreachable!(),
+            },
+            _ => panic!("unexpected payloads: {:?}", payloads),
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_are_rolled_back_or_finalized() {
+        let bot = TestBot::new().await;
+        let mut config = RecordSessionConfig {
+            idle_timeout: 60,
+            ..RecordSessionConfig::default()
+        };
+
+        config.timeout_action = TimeoutAction::Rollback;
+        let uuid = start_expired_session(&bot, 1).await;
+        let record = bot.bot_help.select_record(&uuid).await.unwrap().unwrap();
+        let asset_dir = Path::new(&bot.share_path()).join(image::record_image_dir(&record));
+        std::fs::create_dir_all(&asset_dir).unwrap();
+        std::fs::write(asset_dir.join("a.png"), b"png").unwrap();
+        let payloads = expire_sessions(&config, &bot.bot_help).await.unwrap();
+        assert_eq!(notice(&payloads), "记录超时，已撤销本次记录");
+        assert!(bot.bot_help.select_record(&uuid).await.unwrap().is_none());
+        assert!(!asset_dir.exists());
+
+        config.timeout_action = TimeoutAction::Finalize;
+        let uuid = start_expired_session(&bot, 2).await;
+        let payloads = expire_sessions(&config, &bot.bot_help).await.unwrap();
+        assert_eq!(notice(&payloads), "记录超时，已保存已记录的内容");
+        assert!(bot.bot_help.select_record(&uuid).await.unwrap().is_some());
+
+        let key = SessionKey {
+            group_id: 20001,
+            user_id: 3,
+        };
+        bot.bot_help
+            .sessions
+            .update_status(key, BotStatus::RecordTitle)
+            .await;
+        assert!(expire_sessions(&config, &bot.bot_help)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            bot.bot_help.sessions.status(&key).await,
+            BotStatus::RecordTitle
+        );
+    }
+
+    #[tokio::test]
+    async fn sessions_handling_a_message_do_not_expire() {
+        let bot = TestBot::new().await;
+        let config = RecordSessionConfig {
+            idle_timeout: 60,
+            timeout_action: TimeoutAction::Rollback,
+            ..RecordSessionConfig::default()
+        };
+        let uuid = start_expired_session(&bot, 1).await;
+        let key = SessionKey {
+            group_id: 20001,
+            user_id: 1,
+        };
+
+        let handling = bot.bot_help.sessions.begin_handling(key);
+        assert!(expire_sessions(&config, &bot.bot_help)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            bot.bot_help.sessions.recording_uuid(&key).await.unwrap(),
+            uuid
+        );
+
+        drop(handling);
+        let payloads = expire_sessions(&config, &bot.bot_help).await.unwrap();
+        assert_eq!(notice(&payloads), "记录超时，已撤销本次记录");
+    }
+}
@@ -0,0 +1,221 @@
+use crate::storage::{Content, Record, Storage, UserRole};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 数据只保存在内存中，重启后丢失，用于测试和本地调试
+#[derive(Default)]
+pub struct MemoryStorage {
+    data: Mutex<MemoryData>,
+}
+
+#[derive(Default)]
+struct MemoryData {
+    bot_data: HashMap<String, String>,
+    records: Vec<Record>,
+    content: Vec<Content>,
+    user_roles: Vec<UserRole>,
+}
+
+impl MemoryData {
+    fn bump_revision(&mut self, uuid: &str) {
+        if let Some(record) = self.records.iter_mut().find(|record| record.id == uuid) {
+            record.revision += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn migrate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn select_bot_data(&self, key: &str) -> Result<String> {
+        self.data
+            .lock()
+            .unwrap()
+            .bot_data
+            .get(key)
+            .cloned()
+            .ok_or(anyhow!("bot_data key not found: {}", key))
+    }
+
+    async fn insert_bot_data(&self, key: &str, value: String) -> Result<()> {
+        self.data
+            .lock()
+            .unwrap()
+            .bot_data
+            .entry(key.to_string())
+            .or_insert(value);
+        Ok(())
+    }
+
+    async fn upsert_bot_data(&self, key: &str, value: String) -> Result<()> {
+        self.data
+            .lock()
+            .unwrap()
+            .bot_data
+            .insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn insert_record(&self, id: &str, group_id: i64, title: String) -> Result<()> {
+        self.data.lock().unwrap().records.push(Record {
+            id: id.to_string(),
+            title,
+            remark: None,
+            created_at: Local::now(),
+            group_id,
+            revision: 0,
+        });
+        Ok(())
+    }
+
+    async fn update_record_remark(&self, id: &str, remark: String) -> Result<()> {
+        let mut data = self.data.lock().unwrap();
+        if let Some(record) = data.records.iter_mut().find(|record| record.id == id) {
+            record.remark = Some(remark);
+        }
+        Ok(())
+    }
+
+    async fn select_all_records(&self) -> Result<Vec<Record>> {
+        let mut records = self.data.lock().unwrap().records.clone();
+        records.sort_by_key(|record| record.created_at);
+        Ok(records)
+    }
+
+    async fn select_records_by_date(
+        &self,
+        start_date: DateTime<Local>,
+        end_date: DateTime<Local>,
+    ) -> Result<Vec<Record>> {
+        Ok(self
+            .select_all_records()
+            .await?
+            .into_iter()
+            .filter(|record| record.created_at >= start_date && record.created_at < end_date)
+            .collect())
+    }
+
+    async fn select_record(&self, id: &str) -> Result<Option<Record>> {
+        Ok(self
+            .data
+            .lock()
+            .unwrap()
+            .records
+            .iter()
+            .find(|record| record.id == id)
+            .cloned())
+    }
+
+    async fn select_last_record(&self, group_id: i64) -> Result<Option<Record>> {
+        Ok(self
+            .select_all_records()
+            .await?
+            .into_iter()
+            .rev()
+            .find(|record| record.group_id == group_id))
+    }
+
+    async fn delete_record(&self, id: &str) -> Result<()> {
+        self.data
+            .lock()
+            .unwrap()
+            .records
+            .retain(|record| record.id != id);
+        Ok(())
+    }
+
+    async fn insert_content(
+        &self,
+        uuid: &str,
+        content: String,
+        content_type: String,
+    ) -> Result<()> {
+        let mut data = self.data.lock().unwrap();
+        data.content.push(Content {
+            uuid: uuid.to_string(),
+            content,
+            content_type,
+        });
+        data.bump_revision(uuid);
+        Ok(())
+    }
+
+    async fn select_all_content(&self) -> Result<Vec<Content>> {
+        Ok(self.data.lock().unwrap().content.clone())
+    }
+
+    async fn select_content(&self, uuid: &str) -> Result<Vec<Content>> {
+        Ok(self
+            .select_all_content()
+            .await?
+            .into_iter()
+            .filter(|content| content.uuid == uuid)
+            .collect())
+    }
+
+    async fn update_content(
+        &self,
+        uuid: &str,
+        old_content: &str,
+        new_content: String,
+    ) -> Result<()> {
+        let mut data = self.data.lock().unwrap();
+        for content in data.content.iter_mut() {
+            if content.uuid == uuid && content.content == old_content {
+                content.content = new_content.clone();
+            }
+        }
+        data.bump_revision(uuid);
+        Ok(())
+    }
+
+    async fn delete_content(&self, uuid: &str) -> Result<()> {
+        self.data
+            .lock()
+            .unwrap()
+            .content
+            .retain(|content| content.uuid != uuid);
+        Ok(())
+    }
+
+    async fn select_user_roles(&self, user_id: i64) -> Result<Vec<UserRole>> {
+        Ok(self
+            .select_all_user_roles()
+            .await?
+            .into_iter()
+            .filter(|role| role.user_id == user_id)
+            .collect())
+    }
+
+    async fn select_all_user_roles(&self) -> Result<Vec<UserRole>> {
+        let mut roles = self.data.lock().unwrap().user_roles.clone();
+        roles.sort_by_key(|role| (role.user_id, role.group_id));
+        Ok(roles)
+    }
+
+    async fn upsert_user_role(&self, user_id: i64, group_id: i64, role: String) -> Result<()> {
+        let mut data = self.data.lock().unwrap();
+        data.user_roles
+            .retain(|existing| (existing.user_id, existing.group_id) != (user_id, group_id));
+        data.user_roles.push(UserRole {
+            user_id,
+            group_id,
+            role,
+        });
+        Ok(())
+    }
+
+    async fn delete_user_role(&self, user_id: i64, group_id: i64) -> Result<bool> {
+        let mut data = self.data.lock().unwrap();
+        let before = data.user_roles.len();
+        data.user_roles
+            .retain(|existing| (existing.user_id, existing.group_id) != (user_id, group_id));
+        Ok(data.user_roles.len() < before)
+    }
+}
@@ -0,0 +1,240 @@
+use crate::config::{DataBaseConfig, DataBaseKind};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+
+mod memory;
+mod postgres;
+mod sqlite;
+
+#[derive(sqlx::FromRow, Clone)]
+pub struct Record {
+    pub id: String,
+    pub title: String,
+    pub remark: Option<String>,
+    pub created_at: DateTime<Local>,
+    /// 发起记录的群，为 0 时表示添加此字段之前的记录或不属于任何群
+    pub group_id: i64,
+    /// 记录的内容每次写入或修改时递增，生成时据此判断页面是否需要重新渲染
+    pub revision: i64,
+}
+
+#[derive(sqlx::FromRow, Clone)]
+pub struct Content {
+    pub uuid: String,
+    pub content: String,
+    pub content_type: String,
+}
+
+/// 分配给用户的身份，`group_id` 为 0 时表示全局身份
+#[derive(sqlx::FromRow, Clone)]
+pub struct UserRole {
+    pub user_id: i64,
+    pub group_id: i64,
+    pub role: String,
+}
+
+/// `BotHelp` 用到的全部数据库操作，不同数据库各自实现
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn migrate(&self) -> Result<()>;
+
+    async fn select_bot_data(&self, key: &str) -> Result<String>;
+
+    /// 键不存在时写入，已存在则保持原值
+    async fn insert_bot_data(&self, key: &str, value: String) -> Result<()>;
+
+    /// 键不存在时写入，已存在则覆盖
+    async fn upsert_bot_data(&self, key: &str, value: String) -> Result<()>;
+
+    async fn insert_record(&self, id: &str, group_id: i64, title: String) -> Result<()>;
+
+    async fn update_record_remark(&self, id: &str, remark: String) -> Result<()>;
+
+    async fn select_all_records(&self) -> Result<Vec<Record>>;
+
+    /// 查询 `[start_date, end_date)` 内创建的记录
+    async fn select_records_by_date(
+        &self,
+        start_date: DateTime<Local>,
+        end_date: DateTime<Local>,
+    ) -> Result<Vec<Record>>;
+
+    async fn select_record(&self, id: &str) -> Result<Option<Record>>;
+
+    /// 某个群中最近创建的一条记录
+    async fn select_last_record(&self, group_id: i64) -> Result<Option<Record>>;
+
+    async fn delete_record(&self, id: &str) -> Result<()>;
+
+    async fn insert_content(&self, uuid: &str, content: String, content_type: String)
+        -> Result<()>;
+
+    /// 所有记录的内容，按写入顺序排列
+    async fn select_all_content(&self) -> Result<Vec<Content>>;
+
+    /// 某条记录的内容，按写入顺序排列
+    async fn select_content(&self, uuid: &str) -> Result<Vec<Content>>;
+
+    /// 修改某条记录中的一条内容，用于迁移图片路径
+    async fn update_content(&self, uuid: &str, old_content: &str, new_content: String)
+        -> Result<()>;
+
+    async fn delete_content(&self, uuid: &str) -> Result<()>;
+
+    /// 某个用户在全局和各个群中的身份
+    async fn select_user_roles(&self, user_id: i64) -> Result<Vec<UserRole>>;
+
+    async fn select_all_user_roles(&self) -> Result<Vec<UserRole>>;
+
+    /// 同一用户在同一范围内只保留一个身份
+    async fn upsert_user_role(&self, user_id: i64, group_id: i64, role: String) -> Result<()>;
+
+    /// 返回是否删除了身份
+    async fn delete_user_role(&self, user_id: i64, group_id: i64) -> Result<bool>;
+}
+
+pub async fn connect(config: &DataBaseConfig) -> Result<Box<dyn Storage>> {
+    Ok(match &config.kind {
+        DataBaseKind::Postgres => Box::new(postgres::PgStorage::connect(config).await?),
+        DataBaseKind::Sqlite(path) => Box::new(sqlite::SqliteStorage::connect(path).await?),
+        DataBaseKind::Memory => Box::new(memory::MemoryStorage::default()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    async fn assert_end_is_exclusive(storage: Box<dyn Storage>) {
+        storage.migrate().await.unwrap();
+        storage
+            .insert_record("id", 0, "标题".to_string())
+            .await
+            .unwrap();
+        let created_at = storage.select_all_records().await.unwrap()[0].created_at;
+
+        let before = storage
+            .select_records_by_date(created_at - Duration::days(1), created_at)
+            .await
+            .unwrap();
+        assert!(before.is_empty());
+        let from = storage
+            .select_records_by_date(created_at, created_at + Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(from.len(), 1);
+    }
+
+    async fn assert_last_record_is_per_group(storage: Box<dyn Storage>) {
+        storage.migrate().await.unwrap();
+        for (id, group_id) in [("a1", 1), ("a2", 1), ("b1", 2)] {
+            storage
+                .insert_record(id, group_id, id.to_string())
+                .await
+                .unwrap();
+        }
+
+        let last = storage.select_last_record(1).await.unwrap().unwrap();
+        assert_eq!((last.id.as_str(), last.group_id), ("a2", 1));
+        assert!(storage.select_last_record(3).await.unwrap().is_none());
+    }
+
+    async fn assert_content_bumps_revision(storage: Box<dyn Storage>) {
+        storage.migrate().await.unwrap();
+        for id in ["a", "b"] {
+            storage.insert_record(id, 0, id.to_string()).await.unwrap();
+        }
+        for content in ["旧", "二"] {
+            storage
+                .insert_content("a", content.to_string(), "text".to_string())
+                .await
+                .unwrap();
+        }
+        storage
+            .update_content("a", "旧", "一".to_string())
+            .await
+            .unwrap();
+
+        let contents = storage.select_content("a").await.unwrap();
+        let texts: Vec<_> = contents
+            .iter()
+            .map(|content| content.content.as_str())
+            .collect();
+        assert_eq!(texts, vec!["一", "二"]);
+        let revisions: Vec<_> = storage
+            .select_all_records()
+            .await
+            .unwrap()
+            .iter()
+            .map(|record| (record.id.clone(), record.revision))
+            .collect();
+        assert!(revisions.contains(&("a".to_string(), 3)));
+        assert!(revisions.contains(&("b".to_string(), 0)));
+    }
+
+    #[tokio::test]
+    async fn content_changes_bump_record_revision() {
+        assert_content_bumps_revision(Box::new(memory::MemoryStorage::default())).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bot.db");
+        let sqlite = sqlite::SqliteStorage::connect(path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_content_bumps_revision(Box::new(sqlite)).await;
+    }
+
+    async fn assert_bot_data_upsert_overwrites(storage: Box<dyn Storage>) {
+        storage.migrate().await.unwrap();
+        for (key, value) in [("kept", "1"), ("kept", "2"), ("admin", "1")] {
+            storage
+                .insert_bot_data(key, value.to_string())
+                .await
+                .unwrap();
+        }
+        storage
+            .upsert_bot_data("admin", "2".to_string())
+            .await
+            .unwrap();
+        assert_eq!(storage.select_bot_data("kept").await.unwrap(), "1");
+        assert_eq!(storage.select_bot_data("admin").await.unwrap(), "2");
+    }
+
+    #[tokio::test]
+    async fn bot_data_upsert_overwrites_insert_keeps() {
+        assert_bot_data_upsert_overwrites(Box::new(memory::MemoryStorage::default())).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bot.db");
+        let sqlite = sqlite::SqliteStorage::connect(path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_bot_data_upsert_overwrites(Box::new(sqlite)).await;
+    }
+
+    #[tokio::test]
+    async fn last_record_is_selected_per_group() {
+        assert_last_record_is_per_group(Box::new(memory::MemoryStorage::default())).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bot.db");
+        let sqlite = sqlite::SqliteStorage::connect(path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_last_record_is_per_group(Box::new(sqlite)).await;
+    }
+
+    #[tokio::test]
+    async fn records_by_date_exclude_the_end() {
+        assert_end_is_exclusive(Box::new(memory::MemoryStorage::default())).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bot.db");
+        let sqlite = sqlite::SqliteStorage::connect(path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_end_is_exclusive(Box::new(sqlite)).await;
+    }
+}
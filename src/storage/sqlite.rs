@@ -0,0 +1,240 @@
+use crate::storage::{Content, Record, Storage, UserRole};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+
+/// SQLite 中时间以 `CURRENT_TIMESTAMP` 的 UTC 文本格式保存，且精度只到秒，
+/// 因此范围查询需要使用相同格式的 UTC 时间比较，排序时用 rowid 保证插入顺序
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    pub async fn connect(path: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await?;
+        Ok(SqliteStorage { pool })
+    }
+
+    /// 内容变化时递增所属记录的版本
+    async fn bump_revision(&self, uuid: &str) -> Result<()> {
+        sqlx::query("UPDATE records SET revision = revision + 1 WHERE id = $1")
+            .bind(uuid)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn migrate(&self) -> Result<()> {
+        sqlx::migrate!("./migrations").run(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn select_bot_data(&self, key: &str) -> Result<String> {
+        let row: (String,) = sqlx::query_as("SELECT data_value FROM bot_data WHERE data_key = $1")
+            .bind(key)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.0)
+    }
+
+    async fn insert_bot_data(&self, key: &str, value: String) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO bot_data (data_key, data_value) VALUES ($1, $2) ON CONFLICT (data_key) DO NOTHING",
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_bot_data(&self, key: &str, value: String) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO bot_data (data_key, data_value) VALUES ($1, $2) ON CONFLICT (data_key) DO UPDATE SET data_value = excluded.data_value",
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_record(&self, id: &str, group_id: i64, title: String) -> Result<()> {
+        sqlx::query("INSERT INTO records (id, title, group_id) VALUES ($1, $2, $3)")
+            .bind(id)
+            .bind(title)
+            .bind(group_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_record_remark(&self, id: &str, remark: String) -> Result<()> {
+        sqlx::query("UPDATE records SET remark = $1 WHERE id = $2")
+            .bind(remark)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn select_all_records(&self) -> Result<Vec<Record>> {
+        let rows: Vec<Record> = sqlx::query_as(
+            "SELECT id, title, remark, created_at, group_id, revision FROM records ORDER BY created_at ASC, rowid ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn select_records_by_date(
+        &self,
+        start_date: DateTime<Local>,
+        end_date: DateTime<Local>,
+    ) -> Result<Vec<Record>> {
+        let rows: Vec<Record> = sqlx::query_as(
+            "SELECT id, title, remark, created_at, group_id, revision FROM records WHERE created_at >= $1 and created_at < $2 ORDER BY created_at ASC, rowid ASC",
+        )
+            .bind(start_date.naive_utc())
+            .bind(end_date.naive_utc())
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    async fn select_record(&self, id: &str) -> Result<Option<Record>> {
+        let row: Option<Record> = sqlx::query_as(
+            "SELECT id, title, remark, created_at, group_id, revision FROM records WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    async fn select_last_record(&self, group_id: i64) -> Result<Option<Record>> {
+        let row: Option<Record> = sqlx::query_as(
+            "SELECT id, title, remark, created_at, group_id, revision FROM records WHERE group_id = $1 ORDER BY created_at DESC, rowid DESC LIMIT 1",
+        )
+        .bind(group_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    async fn delete_record(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM records WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn insert_content(
+        &self,
+        uuid: &str,
+        content: String,
+        content_type: String,
+    ) -> Result<()> {
+        sqlx::query("INSERT INTO content (uuid, content, content_type) VALUES ($1, $2, $3)")
+            .bind(uuid)
+            .bind(content)
+            .bind(content_type)
+            .execute(&self.pool)
+            .await?;
+        self.bump_revision(uuid).await
+    }
+
+    async fn select_all_content(&self) -> Result<Vec<Content>> {
+        let rows: Vec<Content> = sqlx::query_as(
+            "SELECT uuid, content, content_type FROM content WHERE delete_status = false ORDER BY create_time ASC, rowid ASC",
+        )
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    async fn select_content(&self, uuid: &str) -> Result<Vec<Content>> {
+        let rows: Vec<Content> = sqlx::query_as(
+            "SELECT uuid, content, content_type FROM content WHERE uuid = $1 AND delete_status = false ORDER BY create_time ASC, rowid ASC",
+        )
+        .bind(uuid)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn update_content(
+        &self,
+        uuid: &str,
+        old_content: &str,
+        new_content: String,
+    ) -> Result<()> {
+        sqlx::query("UPDATE content SET content = $3 WHERE uuid = $1 AND content = $2")
+            .bind(uuid)
+            .bind(old_content)
+            .bind(new_content)
+            .execute(&self.pool)
+            .await?;
+        self.bump_revision(uuid).await
+    }
+
+    async fn delete_content(&self, uuid: &str) -> Result<()> {
+        sqlx::query("DELETE FROM content WHERE uuid = $1")
+            .bind(uuid)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn select_user_roles(&self, user_id: i64) -> Result<Vec<UserRole>> {
+        let rows: Vec<UserRole> = sqlx::query_as(
+            "SELECT user_id, group_id, role FROM user_roles WHERE user_id = $1 ORDER BY group_id ASC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn select_all_user_roles(&self) -> Result<Vec<UserRole>> {
+        let rows: Vec<UserRole> = sqlx::query_as(
+            "SELECT user_id, group_id, role FROM user_roles ORDER BY user_id ASC, group_id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn upsert_user_role(&self, user_id: i64, group_id: i64, role: String) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO user_roles (user_id, group_id, role) VALUES ($1, $2, $3) ON CONFLICT (user_id, group_id) DO UPDATE SET role = excluded.role",
+        )
+        .bind(user_id)
+        .bind(group_id)
+        .bind(role)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_user_role(&self, user_id: i64, group_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM user_roles WHERE user_id = $1 AND group_id = $2")
+            .bind(user_id)
+            .bind(group_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,99 @@
+use futures_util::{SinkExt, StreamExt};
+use onebot_v11::connect::ws::WsConfig;
+use onebot_v11::connect::WsType;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 本地 OneBot v11 正向 WebSocket 服务端，记录收到的 API 调用并按 action 返回预设数据
+#[derive(Clone)]
+pub struct MockOneBot {
+    addr: SocketAddr,
+    responses: Arc<Mutex<HashMap<String, Value>>>,
+    api_calls: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl MockOneBot {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mock = MockOneBot {
+            addr: listener.local_addr().unwrap(),
+            responses: Arc::new(Mutex::new(HashMap::new())),
+            api_calls: Arc::new(Mutex::new(Vec::new())),
+        };
+        let server = mock.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let server = server.clone();
+                tokio::spawn(async move {
+                    let ws_stream = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    let (mut write, mut read) = ws_stream.split();
+                    while let Some(Ok(Message::Text(text))) = read.next().await {
+                        let request: Value = serde_json::from_str(&text).unwrap();
+                        let action = request["action"].as_str().unwrap().to_string();
+                        server
+                            .api_calls
+                            .lock()
+                            .unwrap()
+                            .push((action.clone(), request["params"].clone()));
+                        let data = {
+                            let responses = server.responses.lock().unwrap();
+                            responses
+                                .get(&response_key(&action, &request["params"]))
+                                .or(responses.get(&action))
+                                .cloned()
+                                .unwrap_or(Value::Null)
+                        };
+                        let response = json!({
+                            "status": "ok",
+                            "retcode": 0,
+                            "data": data,
+                            "echo": request["echo"],
+                        });
+                        if write.send(Message::Text(response.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        mock
+    }
+
+    pub fn ws_config(&self) -> WsConfig {
+        WsConfig {
+            host: self.addr.ip().to_string(),
+            port: self.addr.port(),
+            r#type: WsType::Universal,
+            bot_id: None,
+            bot_nick_name: None,
+            access_token: None,
+        }
+    }
+
+    pub fn set_response(&self, action: &str, data: Value) {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(action.to_string(), data);
+    }
+
+    /// 只对参数完全相同的调用生效，优先于 `set_response`
+    pub fn set_response_for(&self, action: &str, params: Value, data: Value) {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(response_key(action, &params), data);
+    }
+
+    pub fn api_calls(&self) -> Vec<(String, Value)> {
+        self.api_calls.lock().unwrap().clone()
+    }
+}
+
+fn response_key(action: &str, params: &Value) -> String {
+    format!("{} {}", action, params)
+}
@@ -0,0 +1,153 @@
+//! 测试辅助：模拟 OneBot v11 服务端、HTTP 服务端以及基于内存数据库的机器人
+
+mod mock_http;
+mod mock_onebot;
+
+pub use mock_http::MockHttpServer;
+pub use mock_onebot::MockOneBot;
+
+use crate::bot_help::BotHelp;
+use crate::config::{CoreConfig, DataBaseKind};
+use crate::message_handle;
+use anyhow::Result;
+use onebot_v11::api::payload::{ApiPayload, SendGroupMsg};
+use onebot_v11::connect::ws::WsConnect;
+use onebot_v11::event::message::{
+    GroupMessage, GroupMessageSender, Message, PrivateMessage, PrivateMessageSender,
+};
+use onebot_v11::MessageSegment;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+pub const SELF_ID: i64 = 10000;
+pub const ADMIN_ID: i64 = 10001;
+
+pub struct TestBot {
+    pub config: CoreConfig,
+    pub bot_help: Arc<BotHelp>,
+    pub onebot: MockOneBot,
+    pub share_dir: TempDir,
+}
+
+impl TestBot {
+    pub async fn new() -> Self {
+        let onebot = MockOneBot::start().await;
+        let share_dir = tempfile::tempdir().unwrap();
+        let mut config = CoreConfig {
+            bot_ws: onebot.ws_config(),
+            ..CoreConfig::default()
+        };
+        config.data_base.kind = DataBaseKind::Memory;
+        config.data_base.init_data.admin = ADMIN_ID;
+        config.data_base.init_data.share_path = share_dir.path().to_string_lossy().to_string();
+        let ws_connect = WsConnect::new(config.bot_ws.clone()).await.unwrap();
+        let bot_help = Arc::new(BotHelp::init(&config.data_base, ws_connect).await.unwrap());
+        TestBot {
+            config,
+            bot_help,
+            onebot,
+            share_dir,
+        }
+    }
+
+    pub fn share_path(&self) -> PathBuf {
+        self.share_dir.path().to_path_buf()
+    }
+
+    pub async fn send_group(
+        &self,
+        group_id: i64,
+        user_id: i64,
+        message: Vec<MessageSegment>,
+    ) -> Result<Option<Vec<ApiPayload>>> {
+        message_handle::handle_message(
+            &self.config,
+            group_message(group_id, user_id, message),
+            self.bot_help.clone(),
+        )
+        .await
+    }
+
+    pub async fn send_private(
+        &self,
+        user_id: i64,
+        message: Vec<MessageSegment>,
+    ) -> Result<Option<Vec<ApiPayload>>> {
+        message_handle::handle_message(
+            &self.config,
+            private_message(user_id, message),
+            self.bot_help.clone(),
+        )
+        .await
+    }
+}
+
+pub fn group_message(group_id: i64, user_id: i64, message: Vec<MessageSegment>) -> Message {
+    Message::GroupMessage(GroupMessage {
+        time: 0,
+        self_id: SELF_ID,
+        post_type: "message".to_string(),
+        message_type: "group".to_string(),
+        sub_type: "normal".to_string(),
+        message_id: 1,
+        group_id,
+        user_id,
+        anonymous: None,
+        message,
+        raw_message: String::new(),
+        font: 0,
+        sender: GroupMessageSender {
+            user_id: Some(user_id),
+            nickname: Some(format!("user{}", user_id)),
+            card: None,
+            sex: None,
+            age: None,
+            area: None,
+            level: None,
+            role: None,
+            title: None,
+        },
+    })
+}
+
+pub fn private_message(user_id: i64, message: Vec<MessageSegment>) -> Message {
+    Message::PrivateMessage(PrivateMessage {
+        time: 0,
+        self_id: SELF_ID,
+        post_type: "message".to_string(),
+        message_type: "private".to_string(),
+        sub_type: "friend".to_string(),
+        message_id: 1,
+        user_id,
+        message,
+        raw_message: String::new(),
+        font: 0,
+        sender: PrivateMessageSender {
+            user_id: Some(user_id),
+            nickname: Some(format!("user{}", user_id)),
+            sex: None,
+            age: None,
+        },
+    })
+}
+
+/// @机器人 后跟一段文本，即群聊中的指令格式
+pub fn at_bot(text: &str) -> Vec<MessageSegment> {
+    vec![
+        MessageSegment::at(SELF_ID.to_string()),
+        MessageSegment::text(text),
+    ]
+}
+
+pub fn text(text: &str) -> Vec<MessageSegment> {
+    vec![MessageSegment::text(text)]
+}
+
+pub fn group_reply(group_id: i64, message: Vec<MessageSegment>) -> Option<Vec<ApiPayload>> {
+    Some(vec![ApiPayload::SendGroupMsg(SendGroupMsg {
+        group_id,
+        message,
+        auto_escape: false,
+    })])
+}
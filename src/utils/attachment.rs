@@ -0,0 +1,253 @@
+use crate::bot_help::BotHelp;
+use crate::config::AttachmentConfig;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use onebot_v11::api::payload::{ApiPayload, GetFile};
+use onebot_v11::api::resp::ApiRespData;
+use std::fmt;
+use std::fs::{create_dir_all, write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 群文件或视频消息中可用于下载的信息
+pub struct Attachment {
+    pub name: String,
+    pub url: Option<String>,
+    /// 没有 URL 时通过 `get_file` 获取文件内容
+    pub file_id: Option<String>,
+    pub size: Option<u64>,
+}
+
+/// 附件不满足配置中的限制
+#[derive(Debug, PartialEq)]
+pub enum AttachmentRejected {
+    Extension { name: String },
+    TooLarge { name: String, limit: u64 },
+}
+
+impl fmt::Display for AttachmentRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttachmentRejected::Extension { name } => {
+                write!(f, "不支持记录此类型的文件: {}", name)
+            }
+            AttachmentRejected::TooLarge { name, limit } => {
+                write!(f, "文件超过大小限制（{} 字节）: {}", limit, name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttachmentRejected {}
+
+/// 下载附件到 `save_dir`，保留原文件名（重名时追加序号），返回保存的文件名
+pub async fn save_attachment(
+    config: &AttachmentConfig,
+    bot_help: Arc<BotHelp>,
+    attachment: &Attachment,
+    save_dir: &Path,
+) -> Result<String> {
+    let name = file_name(&attachment.name);
+    check_extension(config, &name)?;
+    if let Some(size) = attachment.size {
+        check_size(config, &name, size)?;
+    }
+    let bytes = match (&attachment.url, &attachment.file_id) {
+        (Some(url), _) => download(config, &name, url).await?,
+        (None, Some(file_id)) => get_file(config, &name, bot_help, file_id).await?,
+        (None, None) => return Err(anyhow!("no url or file id for {}", name)),
+    };
+    check_size(config, &name, bytes.len() as u64)?;
+    create_dir_all(save_dir)?;
+    let save_name = unique_name(save_dir, &name);
+    write(save_dir.join(&save_name), bytes)?;
+    Ok(save_name)
+}
+
+fn check_extension(config: &AttachmentConfig, name: &str) -> Result<()> {
+    if config.allowed_extensions.is_empty() {
+        return Ok(());
+    }
+    let extension = Path::new(name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if config
+        .allowed_extensions
+        .iter()
+        .any(|allowed| allowed.to_lowercase() == extension)
+    {
+        return Ok(());
+    }
+    Err(AttachmentRejected::Extension {
+        name: name.to_string(),
+    }
+    .into())
+}
+
+fn check_size(config: &AttachmentConfig, name: &str, size: u64) -> Result<()> {
+    if size > config.max_size {
+        return Err(AttachmentRejected::TooLarge {
+            name: name.to_string(),
+            limit: config.max_size,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// 边下载边检查大小，没有 Content-Length 时也不会把超限的文件读入内存
+async fn download(config: &AttachmentConfig, name: &str, url: &str) -> Result<Vec<u8>> {
+    let mut response = reqwest::get(url).await?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Download Attachment Error, Status Code: {}",
+            response.status()
+        ));
+    }
+    if let Some(length) = response.content_length() {
+        check_size(config, name, length)?;
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        check_size(config, name, bytes.len() as u64)?;
+    }
+    Ok(bytes)
+}
+
+async fn get_file(
+    config: &AttachmentConfig,
+    name: &str,
+    bot_help: Arc<BotHelp>,
+    file_id: &str,
+) -> Result<Vec<u8>> {
+    let resp = bot_help
+        .ws_connect
+        .clone()
+        .call_api(ApiPayload::GetFile(GetFile {
+            file_id: file_id.to_string(),
+        }))
+        .await?;
+    let ApiRespData::GetFileResponse(resp) = resp.data else {
+        return Err(anyhow!("Get File Failed"));
+    };
+    check_size(config, name, resp.file_size)?;
+    // 上报的文件大小不可信，解码前按编码长度检查
+    check_size(config, name, base64_decoded_len(&resp.base64))?;
+    Ok(STANDARD.decode(resp.base64)?)
+}
+
+/// base64 解码后的字节数，不实际解码
+fn base64_decoded_len(encoded: &str) -> u64 {
+    let padding = encoded
+        .bytes()
+        .rev()
+        .take_while(|byte| *byte == b'=')
+        .count();
+    (encoded.len() as u64 * 3 / 4).saturating_sub(padding as u64)
+}
+
+/// 去掉文件名中的目录部分
+fn file_name(name: &str) -> String {
+    Path::new(name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+fn unique_name(dir: &Path, name: &str) -> String {
+    if !dir.join(name).exists() {
+        return name.to_string();
+    }
+    let path = Path::new(name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|index| format!("{}-{}{}", stem, index, extension))
+        .find(|candidate| !dir.join(candidate).exists())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn extension_and_size_limits() {
+        let config = AttachmentConfig {
+            max_size: 10,
+            allowed_extensions: vec!["PDF".to_string()],
+        };
+        assert!(check_extension(&config, "报告.pdf").is_ok());
+        let e = check_extension(&config, "setup.exe").unwrap_err();
+        assert_eq!(
+            e.downcast_ref::<AttachmentRejected>(),
+            Some(&AttachmentRejected::Extension {
+                name: "setup.exe".to_string()
+            })
+        );
+        assert!(check_size(&config, "报告.pdf", 10).is_ok());
+        let e = check_size(&config, "报告.pdf", 11).unwrap_err();
+        assert_eq!(e.to_string(), "文件超过大小限制（10 字节）: 报告.pdf");
+    }
+
+    #[tokio::test]
+    async fn download_without_content_length_is_limited() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            for _ in 0..4 {
+                if stream.write_all(&[b'x'; 8]).await.is_err() {
+                    return;
+                }
+            }
+        });
+        let config = AttachmentConfig {
+            max_size: 10,
+            allowed_extensions: Vec::new(),
+        };
+        let e = download(&config, "a.bin", &format!("http://{}/a.bin", addr))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            e.downcast_ref::<AttachmentRejected>(),
+            Some(&AttachmentRejected::TooLarge {
+                name: "a.bin".to_string(),
+                limit: 10
+            })
+        );
+    }
+
+    #[test]
+    fn base64_length_is_known_before_decoding() {
+        for data in [&b""[..], b"a", b"ab", b"abc", b"abcd"] {
+            let encoded = STANDARD.encode(data);
+            assert_eq!(base64_decoded_len(&encoded), data.len() as u64);
+        }
+        assert_eq!(base64_decoded_len("YWI"), 2);
+    }
+
+    #[test]
+    fn duplicate_names_get_a_suffix() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(file_name("../a/b/notes.txt"), "notes.txt");
+        assert_eq!(unique_name(dir.path(), "notes.txt"), "notes.txt");
+        write(dir.path().join("notes.txt"), b"1").unwrap();
+        write(dir.path().join("notes-1.txt"), b"2").unwrap();
+        assert_eq!(unique_name(dir.path(), "notes.txt"), "notes-2.txt");
+    }
+}
@@ -0,0 +1,18 @@
+use super::{canonical_url, string_at, with_scheme, Card, CardParser};
+use serde_json::Value;
+
+/// QQ 小程序（`meta.detail_1`），`title` 为小程序名称，`desc` 才是分享内容的标题
+pub struct MiniApp;
+
+impl CardParser for MiniApp {
+    fn parse(&self, card: &Value) -> Option<Card> {
+        let url = string_at(card, "/meta/detail_1/qqdocurl")
+            .or_else(|| string_at(card, "/meta/detail_1/url"))?;
+        Some(Card {
+            title: string_at(card, "/meta/detail_1/desc")?,
+            description: string_at(card, "/meta/detail_1/title").unwrap_or_default(),
+            url: canonical_url(&url),
+            preview: string_at(card, "/meta/detail_1/preview").map(with_scheme),
+        })
+    }
+}
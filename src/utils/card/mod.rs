@@ -0,0 +1,188 @@
+use serde_json::Value;
+use std::sync::LazyLock;
+use url::Url;
+
+mod miniapp;
+mod music;
+mod news;
+mod wechat;
+
+/// 从 QQ ark 卡片（`json` 消息段）中提取的分享信息
+#[derive(Debug, PartialEq)]
+pub struct Card {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    pub preview: Option<String>,
+}
+
+/// 卡片解析器，不是自己支持的卡片时返回 `None`
+pub trait CardParser: Send + Sync {
+    fn parse(&self, card: &Value) -> Option<Card>;
+}
+
+/// 按顺序尝试各个解析器，越具体的解析器越靠前
+pub struct CardRegistry {
+    parsers: Vec<Box<dyn CardParser>>,
+}
+
+static REGISTRY: LazyLock<CardRegistry> = LazyLock::new(CardRegistry::default);
+
+impl CardRegistry {
+    pub fn new(parsers: Vec<Box<dyn CardParser>>) -> Self {
+        CardRegistry { parsers }
+    }
+
+    pub fn parse(&self, json: &str) -> Option<Card> {
+        let card: Value = serde_json::from_str(json).ok()?;
+        self.parsers.iter().find_map(|parser| parser.parse(&card))
+    }
+}
+
+impl Default for CardRegistry {
+    fn default() -> Self {
+        CardRegistry::new(vec![
+            Box::new(wechat::WeChatArticle),
+            Box::new(miniapp::MiniApp),
+            Box::new(music::Music),
+            Box::new(news::News),
+        ])
+    }
+}
+
+/// 使用内置解析器解析卡片
+pub fn parse_card(json: &str) -> Option<Card> {
+    REGISTRY.parse(json)
+}
+
+fn string_at(card: &Value, pointer: &str) -> Option<String> {
+    card.pointer(pointer)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// 补全缺少的协议头
+fn with_scheme(url: String) -> String {
+    if url.contains("://") {
+        url
+    } else {
+        format!("https://{}", url)
+    }
+}
+
+const TRACKING_PARAMS: [&str; 10] = [
+    "share_source",
+    "share_medium",
+    "share_plat",
+    "share_session_id",
+    "share_tag",
+    "share_from",
+    "bbid",
+    "ts",
+    "spm_id_from",
+    "vd_source",
+];
+
+/// 去掉分享时附带的跟踪参数，保留锚点（部分站点用其路由），解析失败时原样返回
+fn canonical_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(&with_scheme(url.to_string())) else {
+        return url.to_string();
+    };
+    let query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    if query.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(query);
+    }
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(title: &str, description: &str, url: &str, preview: Option<&str>) -> Card {
+        Card {
+            title: title.to_string(),
+            description: description.to_string(),
+            url: url.to_string(),
+            preview: preview.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn builtin_parsers_extract_fixture_cards() {
+        let cases = [
+            (
+                include_str!("fixtures/wechat.json"),
+                card(
+                    "一篇文章",
+                    "desc",
+                    "https://mp.weixin.qq.com/s?__biz=BIZ&mid=1&idx=2&sn=SN",
+                    Some("https://example.com/preview.png"),
+                ),
+            ),
+            (
+                include_str!("fixtures/zhihu.json"),
+                card(
+                    "如何学习 Rust？",
+                    "先读完 The Book，再动手写点东西。",
+                    "https://www.zhihu.com/question/123/answer/456",
+                    Some("https://pic1.zhimg.com/v2-preview.jpg"),
+                ),
+            ),
+            (
+                include_str!("fixtures/bilibili_miniapp.json"),
+                card(
+                    "【Rust】从零实现一个 Web 框架",
+                    "哔哩哔哩",
+                    "https://b23.tv/AbCdEfG",
+                    Some("https://pubminishare-30161.picsz.qpic.cn/preview"),
+                ),
+            ),
+            (
+                include_str!("fixtures/music.json"),
+                card(
+                    "晴天",
+                    "周杰伦",
+                    "https://y.qq.com/n/ryqq/songDetail/0039MnYb0qxYhV?songtype=0",
+                    Some("https://y.qq.com/music/photo_new/T002R300x300M000.jpg"),
+                ),
+            ),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(parse_card(json), Some(expected));
+        }
+    }
+
+    #[test]
+    fn unknown_cards_are_not_parsed() {
+        assert_eq!(
+            parse_card(r#"{"app":"com.tencent.multimsg","meta":{}}"#),
+            None
+        );
+        assert_eq!(parse_card("not json"), None);
+    }
+
+    #[test]
+    fn tracking_params_are_removed() {
+        assert_eq!(
+            canonical_url("https://example.com/a?id=1&utm_source=qq&share_source=qq#top"),
+            "https://example.com/a?id=1#top"
+        );
+        assert_eq!(
+            canonical_url("https://x.example/?utm_medium=share#/detail/123"),
+            "https://x.example/#/detail/123"
+        );
+        assert_eq!(
+            canonical_url("m.q.qq.com/a/s/xyz"),
+            "https://m.q.qq.com/a/s/xyz"
+        );
+    }
+}
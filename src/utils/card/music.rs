@@ -0,0 +1,16 @@
+use super::{canonical_url, string_at, with_scheme, Card, CardParser};
+use serde_json::Value;
+
+/// 音乐分享（`meta.music`），描述为歌手
+pub struct Music;
+
+impl CardParser for Music {
+    fn parse(&self, card: &Value) -> Option<Card> {
+        Some(Card {
+            title: string_at(card, "/meta/music/title")?,
+            description: string_at(card, "/meta/music/desc").unwrap_or_default(),
+            url: canonical_url(&string_at(card, "/meta/music/jumpUrl")?),
+            preview: string_at(card, "/meta/music/preview").map(with_scheme),
+        })
+    }
+}
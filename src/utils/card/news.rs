@@ -0,0 +1,16 @@
+use super::{canonical_url, string_at, with_scheme, Card, CardParser};
+use serde_json::Value;
+
+/// 通用图文分享（`meta.news`），如知乎、哔哩哔哩网页分享
+pub struct News;
+
+impl CardParser for News {
+    fn parse(&self, card: &Value) -> Option<Card> {
+        Some(Card {
+            title: string_at(card, "/meta/news/title")?,
+            description: string_at(card, "/meta/news/desc").unwrap_or_default(),
+            url: canonical_url(&string_at(card, "/meta/news/jumpUrl")?),
+            preview: string_at(card, "/meta/news/preview").map(with_scheme),
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use super::{string_at, with_scheme, Card, CardParser};
+use anyhow::{Error, Result};
+use serde_json::Value;
+use url::Url;
+
+/// 微信公众号文章，链接只保留定位文章所需的参数
+pub struct WeChatArticle;
+
+impl CardParser for WeChatArticle {
+    fn parse(&self, card: &Value) -> Option<Card> {
+        let jump_url = string_at(card, "/meta/news/jumpUrl")?;
+        if Url::parse(&jump_url).ok()?.host_str() != Some("mp.weixin.qq.com") {
+            return None;
+        }
+        Some(Card {
+            title: string_at(card, "/meta/news/title")?,
+            description: string_at(card, "/meta/news/desc").unwrap_or_default(),
+            url: get_short_wechat_share_url(&jump_url).ok()?,
+            preview: string_at(card, "/meta/news/preview").map(with_scheme),
+        })
+    }
+}
+
+fn get_short_wechat_share_url(url_str: &str) -> Result<String> {
+    let url = Url::parse(url_str)?;
+    let biz = url
+        .query_pairs()
+        .find(|(key, _)| key == "__biz")
+        .ok_or(Error::msg("No __biz"))?
+        .1;
+    let mid = url
+        .query_pairs()
+        .find(|(key, _)| key == "mid")
+        .ok_or(Error::msg("No mid"))?
+        .1;
+    let idx = url
+        .query_pairs()
+        .find(|(key, _)| key == "idx")
+        .ok_or(Error::msg("No idx"))?
+        .1;
+    let sn = url
+        .query_pairs()
+        .find(|(key, _)| key == "sn")
+        .ok_or(Error::msg("No sn"))?
+        .1;
+    Ok(format!(
+        "https://mp.weixin.qq.com/s?__biz={}&mid={}&idx={}&sn={}",
+        biz, mid, idx, sn
+    ))
+}
@@ -0,0 +1,47 @@
+use crate::config::ForgeConfig;
+use crate::utils::forge::{api_url, Forge, PullRequest};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+pub struct Gitea {
+    config: ForgeConfig,
+    client: Client,
+}
+
+impl Gitea {
+    pub fn new(config: ForgeConfig) -> Self {
+        Gitea {
+            config,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Forge for Gitea {
+    async fn open_pull_request(&self, request: &PullRequest) -> Result<String> {
+        let path = format!("/repos/{}/pulls", self.config.repository);
+        let response: Value = self
+            .client
+            .post(api_url(&self.config, &path))
+            .header(AUTHORIZATION, format!("token {}", self.config.token))
+            .json(&json!({
+                "title": request.title,
+                "head": request.head,
+                "base": request.base,
+                "body": request.body,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        response["html_url"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(anyhow!("Gitea pull request response without html_url"))
+    }
+}
@@ -0,0 +1,49 @@
+use crate::config::ForgeConfig;
+use crate::utils::forge::{api_url, Forge, PullRequest};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::header::{ACCEPT, USER_AGENT};
+use reqwest::Client;
+use serde_json::{json, Value};
+
+pub struct GitHub {
+    config: ForgeConfig,
+    client: Client,
+}
+
+impl GitHub {
+    pub fn new(config: ForgeConfig) -> Self {
+        GitHub {
+            config,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Forge for GitHub {
+    async fn open_pull_request(&self, request: &PullRequest) -> Result<String> {
+        let path = format!("/repos/{}/pulls", self.config.repository);
+        let response: Value = self
+            .client
+            .post(api_url(&self.config, &path))
+            .bearer_auth(&self.config.token)
+            .header(ACCEPT, "application/vnd.github+json")
+            .header(USER_AGENT, "simple-docs-bot")
+            .json(&json!({
+                "title": request.title,
+                "head": request.head,
+                "base": request.base,
+                "body": request.body,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        response["html_url"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(anyhow!("GitHub pull request response without html_url"))
+    }
+}
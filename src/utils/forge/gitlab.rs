@@ -0,0 +1,50 @@
+use crate::config::ForgeConfig;
+use crate::utils::forge::{api_url, Forge, PullRequest};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+pub struct GitLab {
+    config: ForgeConfig,
+    client: Client,
+}
+
+impl GitLab {
+    pub fn new(config: ForgeConfig) -> Self {
+        GitLab {
+            config,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Forge for GitLab {
+    async fn open_pull_request(&self, request: &PullRequest) -> Result<String> {
+        // 项目路径作为 ID 使用时需要转义 `/`
+        let path = format!(
+            "/projects/{}/merge_requests",
+            self.config.repository.replace('/', "%2F")
+        );
+        let response: Value = self
+            .client
+            .post(api_url(&self.config, &path))
+            .header("PRIVATE-TOKEN", &self.config.token)
+            .json(&json!({
+                "source_branch": request.head,
+                "target_branch": request.base,
+                "title": request.title,
+                "description": request.body,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        response["web_url"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(anyhow!("GitLab merge request response without web_url"))
+    }
+}
@@ -0,0 +1,117 @@
+use crate::config::{ForgeConfig, ForgeKind};
+use anyhow::Result;
+use async_trait::async_trait;
+
+mod gitea;
+mod github;
+mod gitlab;
+
+pub struct PullRequest {
+    /// 推送的分支
+    pub head: String,
+    /// 合并到的分支
+    pub base: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// 代码托管平台，发起合并请求并返回其网页地址
+#[async_trait]
+pub trait Forge: Send + Sync {
+    async fn open_pull_request(&self, request: &PullRequest) -> Result<String>;
+}
+
+pub fn forge(config: &ForgeConfig) -> Box<dyn Forge> {
+    match config.kind {
+        ForgeKind::GitHub => Box::new(github::GitHub::new(config.clone())),
+        ForgeKind::Gitea => Box::new(gitea::Gitea::new(config.clone())),
+        ForgeKind::GitLab => Box::new(gitlab::GitLab::new(config.clone())),
+    }
+}
+
+fn api_url(config: &ForgeConfig, path: &str) -> String {
+    format!("{}{}", config.api_url.trim_end_matches('/'), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockHttpServer;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn each_forge_opens_a_pull_request() {
+        let cases = [
+            (
+                ForgeKind::GitHub,
+                "/repos/owner/docs/pulls",
+                ("authorization", "Bearer secret"),
+                json!({"html_url": "https://github.com/owner/docs/pull/1"}),
+                ("head", "base"),
+            ),
+            (
+                ForgeKind::Gitea,
+                "/repos/owner/docs/pulls",
+                ("authorization", "token secret"),
+                json!({"html_url": "https://gitea.example.com/owner/docs/pulls/1"}),
+                ("head", "base"),
+            ),
+            (
+                ForgeKind::GitLab,
+                "/projects/owner%2Fdocs/merge_requests",
+                ("private-token", "secret"),
+                json!({"web_url": "https://gitlab.example.com/owner/docs/-/merge_requests/1"}),
+                ("source_branch", "target_branch"),
+            ),
+        ];
+        for (kind, path, (header, value), response, (head_key, base_key)) in cases {
+            let http = MockHttpServer::start().await;
+            http.route(
+                "POST",
+                path,
+                201,
+                "application/json",
+                response.to_string().as_bytes(),
+            );
+            let config = ForgeConfig {
+                kind,
+                api_url: http.url("/"),
+                repository: "owner/docs".to_string(),
+                token: "secret".to_string(),
+                branch_prefix: "bot/".to_string(),
+            };
+            let request = PullRequest {
+                head: "bot/20261018-220000".to_string(),
+                base: "main".to_string(),
+                title: "20261018 bot publish".to_string(),
+                body: "summary".to_string(),
+            };
+            let url = forge(&config).open_pull_request(&request).await.unwrap();
+
+            let expected_url = response.as_object().unwrap().values().next().unwrap();
+            assert_eq!(url, expected_url.as_str().unwrap());
+            let requests = http.requests();
+            assert_eq!(requests[0].path, path);
+            assert_eq!(requests[0].headers[header], value);
+            let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+            assert_eq!(body[head_key], "bot/20261018-220000");
+            assert_eq!(body[base_key], "main");
+        }
+    }
+
+    #[tokio::test]
+    async fn forge_error_status_is_an_error() {
+        let http = MockHttpServer::start().await;
+        let config = ForgeConfig {
+            api_url: http.url(""),
+            ..ForgeConfig::default()
+        };
+        let request = PullRequest {
+            head: "bot/1".to_string(),
+            base: "main".to_string(),
+            title: String::new(),
+            body: String::new(),
+        };
+        assert!(forge(&config).open_pull_request(&request).await.is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use crate::bot_help::BotHelp;
+use crate::utils::reply_message;
+use anyhow::{anyhow, Result};
+use onebot_v11::api::payload::{ApiPayload, GetForwardMsg};
+use onebot_v11::api::resp::{ApiRespData, MessageSender};
+use onebot_v11::MessageSegment;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+
+/// 合并转发中的一条消息，以 JSON 数组保存在 `forward` 类型的内容中
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ForwardMessage {
+    pub sender: String,
+    pub parts: Vec<ForwardPart>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ForwardPart {
+    Text {
+        text: String,
+    },
+    /// 相对于分享目录的图片路径
+    Image {
+        path: String,
+    },
+}
+
+pub struct ForwardNode {
+    pub sender: String,
+    pub message: Vec<MessageSegment>,
+}
+
+/// onebot_v11 中带消息 ID 的 `node` 与自定义 `node` 同名，只能解析出前者，因此逐条通过 `get_msg` 获取内容
+pub async fn get_forward_nodes(id: &str, bot_help: Arc<BotHelp>) -> Result<Vec<ForwardNode>> {
+    let resp = bot_help
+        .ws_connect
+        .clone()
+        .call_api(ApiPayload::GetForwardMsg(GetForwardMsg {
+            id: id.to_string(),
+        }))
+        .await?;
+    let ApiRespData::GetForwardMsgResponse(resp) = resp.data else {
+        return Err(anyhow!("Get Forward Message Failed"));
+    };
+    let mut nodes = Vec::new();
+    for segment in resp.message {
+        match segment {
+            MessageSegment::Node { data } => {
+                let original =
+                    reply_message::get_reply_original_message(data.id, bot_help.clone()).await?;
+                nodes.push(ForwardNode {
+                    sender: sender_name(&original.sender),
+                    message: original.message,
+                });
+            }
+            MessageSegment::CustomNode { data } => nodes.push(ForwardNode {
+                sender: data
+                    .name
+                    .or(data.uin.map(|uin| uin.to_string()))
+                    .unwrap_or_default(),
+                message: data.content,
+            }),
+            other => warn!("Unsupported Forward Node: {:?}", other),
+        }
+    }
+    Ok(nodes)
+}
+
+fn sender_name(sender: &MessageSender) -> String {
+    sender
+        .card
+        .clone()
+        .filter(|card| !card.is_empty())
+        .or(sender.nickname.clone())
+        .or(sender.user_id.map(|user_id| user_id.to_string()))
+        .unwrap_or_default()
+}
@@ -1,115 +1,928 @@
-use crate::config::GitConfig;
+use crate::bot_help::BotHelp;
+use crate::config::{GitConfig, GitCredential, PublishMode};
+use crate::generator;
+use crate::utils::forge::{self, PullRequest};
 use anyhow::{anyhow, Result};
 use chrono::Local;
+use git2::build::CheckoutBuilder;
+use git2::{
+    AnnotatedCommit, Commit, Config, Cred, CredentialType, Delta, ErrorCode, FetchOptions, Index,
+    IndexAddOption, Oid, PushOptions, RemoteCallbacks, Repository, Signature,
+};
 use onebot_v11::api::payload::{ApiPayload, SendPrivateMsg};
 use onebot_v11::MessageSegment;
-use std::process::Command;
-use tracing::log::{error, info};
-
-pub async fn auto_git_task(config: &GitConfig, admin_id: i64) -> Result<Option<Vec<ApiPayload>>> {
-    let dir = config.repository_dir.clone();
-    let username = config.username.clone();
-    let password = config.password.clone().replace("@", "%40");
-    let url = config.url.clone();
-    let output = Command::new("git")
-        .current_dir(&dir)
-        .arg("add")
-        .arg(".")
-        .output()?;
-    if !output.status.success() {
-        let err_msg = format!(
-            "execute `git add .` error: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        error!("{}", &err_msg);
-        return Err(anyhow!(err_msg));
-    }
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::log::{error, info, warn};
 
-    let output = Command::new("git")
-        .current_dir(&dir)
-        .arg("commit")
-        .arg("-m")
-        .arg(Local::now().format("%Y%m%d bot commit").to_string())
-        .output()?;
-    if !output.status.success() {
-        let err_msg = format!(
-            "execute `git commit` error: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        error!("{}", &err_msg);
-        return Err(anyhow!(err_msg));
-    }
+/// 拉取远程分支时记录到的位置，每次同步前先删除，避免使用上一次的结果
+const REMOTE_TRACKING_REF: &str = "refs/simple-docs-bot/remote";
+/// 合并请求模式下已推送、但还没有成功发起合并请求的提交，后接远程分支名
+const PUSHED_REF_PREFIX: &str = "refs/simple-docs-bot/pushed/";
+/// 合并请求模式下最近一次成功发起合并请求的提交
+const PULL_REQUEST_REF: &str = "refs/simple-docs-bot/pull-request";
 
-    let output = Command::new("git")
-        .current_dir(dir)
-        .arg("push")
-        .arg(format!("https://{username}:{password}@{url}"))
-        .output()?;
-    if !output.status.success() {
-        let err_msg = format!(
-            "execute `git push` error: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        error!("{}", &err_msg);
-        return Err(anyhow!(err_msg));
+/// 变基时与远程修改冲突，已放弃变基，本地提交保留
+#[derive(Debug)]
+pub struct GitConflict {
+    pub paths: Vec<String>,
+}
+
+impl fmt::Display for GitConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "git rebase conflict: {}", self.paths.join(", "))
     }
-    info!("{}", "auto git task complete");
+}
+
+impl std::error::Error for GitConflict {}
+
+/// 一次推送带到远程仓库的变化，相对于推送前的远程分支
+#[derive(Debug, PartialEq)]
+pub struct PushSummary {
+    pub commit: String,
+    /// 本地当前分支
+    pub branch: String,
+    /// 推送到的远程分支，合并请求模式下为新建的分支
+    pub remote_branch: String,
+    pub added: usize,
+    pub modified: usize,
+    pub deleted: usize,
+    /// 生成的记录列表不在仓库中时为 None
+    pub records: Option<RecordChanges>,
+}
+
+/// 推送前后生成的记录列表的差异
+#[derive(Debug, PartialEq)]
+pub struct RecordChanges {
+    pub added: usize,
+    pub deleted: usize,
+}
+
+pub async fn auto_git_task(
+    config: &GitConfig,
+    admin_id: i64,
+    bot_help: &BotHelp,
+) -> Result<Option<Vec<ApiPayload>>> {
+    let task_config = config.clone();
+    let records_path = records_path(config, &bot_help.share_path().await?);
+    let reply = match tokio::task::spawn_blocking(move || {
+        commit_and_push(&task_config, records_path.as_deref())
+    })
+    .await?
+    {
+        Ok(None) => "git任务完成，没有需要提交的修改".to_string(),
+        Ok(Some(summary)) => {
+            let mut reply = format!(
+                "git任务完成\n提交：{}\n新增文件：{}，修改文件：{}，删除文件：{}",
+                summary.commit, summary.added, summary.modified, summary.deleted,
+            );
+            if let Some(records) = &summary.records {
+                reply.push_str(&format!(
+                    "\n新增记录：{}，删除记录：{}",
+                    records.added, records.deleted
+                ));
+            }
+            if let PublishMode::PullRequest(forge_config) = &config.publish {
+                let request = PullRequest {
+                    head: summary.remote_branch.clone(),
+                    base: summary.branch.clone(),
+                    title: format!("{} bot publish", Local::now().format("%Y%m%d")),
+                    body: reply.clone(),
+                };
+                let url = forge::forge(forge_config)
+                    .open_pull_request(&request)
+                    .await?;
+                let task_config = config.clone();
+                let remote_branch = summary.remote_branch.clone();
+                tokio::task::spawn_blocking(move || {
+                    mark_pull_request_opened(&task_config, &remote_branch)
+                })
+                .await??;
+                reply.push_str(&format!("\n合并请求：{}", url));
+            }
+            reply
+        }
+        Err(e) => {
+            let Some(conflict) = e.downcast_ref::<GitConflict>() else {
+                return Err(e);
+            };
+            error!("{}", conflict);
+            format!(
+                "git任务失败，远程仓库的以下文件与本地修改冲突，请手动处理后重试：\n{}",
+                conflict.paths.join("\n")
+            )
+        }
+    };
+    info!("auto git task complete: {}", reply);
     Ok(Some(vec![ApiPayload::SendPrivateMsg(SendPrivateMsg {
         user_id: admin_id,
-        message: vec![MessageSegment::text("git任务完成")],
+        message: vec![MessageSegment::text(reply)],
         auto_escape: false,
     })]))
 }
 
+/// 生成的记录列表相对于仓库根目录的路径，分享目录不在仓库中时为 None
+fn records_path(config: &GitConfig, share_path: &str) -> Option<PathBuf> {
+    let repository = fs::canonicalize(&config.repository_dir).ok()?;
+    let share = fs::canonicalize(share_path).ok()?;
+    let relative = share.strip_prefix(repository).ok()?;
+    Some(relative.join(generator::RECORDS_FILE_NAME))
+}
+
+/// 提交、同步并推送，本地与远程分支一致时返回 None；
+/// 合并请求模式下已为当前提交发起过合并请求时也返回 None，
+/// 已推送但发起合并请求失败时沿用上次推送的分支
+fn commit_and_push(config: &GitConfig, records_path: Option<&Path>) -> Result<Option<PushSummary>> {
+    let repo = Repository::open(&config.repository_dir)?;
+    let message = Local::now().format("%Y%m%d bot commit").to_string();
+    commit_all(&repo, config, &message)?;
+    let branch = current_branch(&repo)?;
+    let upstream = sync_with_remote(&repo, config)?;
+    let head = match repo.head() {
+        Ok(head) => head.peel_to_commit()?,
+        Err(e) if e.code() == ErrorCode::UnbornBranch => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if upstream == Some(head.id()) {
+        return Ok(None);
+    }
+    let remote_branch = match &config.publish {
+        PublishMode::Push => branch.clone(),
+        PublishMode::PullRequest(_) if ref_target(&repo, PULL_REQUEST_REF)? == Some(head.id()) => {
+            return Ok(None)
+        }
+        PublishMode::PullRequest(forge_config) => match pushed_branch(&repo, head.id())? {
+            Some(pushed) => pushed,
+            None => format!(
+                "{}{}",
+                forge_config.branch_prefix,
+                Local::now().format("%Y%m%d-%H%M%S")
+            ),
+        },
+    };
+    let upstream = upstream.map(|oid| repo.find_commit(oid)).transpose()?;
+    let mut summary = summarize(&repo, upstream.as_ref(), &head, records_path)?;
+    push(&repo, config, &branch, &remote_branch)?;
+    if let PublishMode::PullRequest(_) = config.publish {
+        record_pushed_branch(&repo, &remote_branch, head.id())?;
+    }
+    summary.branch = branch;
+    summary.remote_branch = remote_branch;
+    Ok(Some(summary))
+}
+
+fn ref_target(repo: &Repository, name: &str) -> Result<Option<Oid>> {
+    match repo.find_reference(name) {
+        Ok(reference) => Ok(reference.target()),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 已推送到 `commit` 但还没有发起合并请求的远程分支
+fn pushed_branch(repo: &Repository, commit: Oid) -> Result<Option<String>> {
+    for reference in repo.references()? {
+        let reference = reference?;
+        let Some(branch) = reference
+            .name()
+            .and_then(|name| name.strip_prefix(PUSHED_REF_PREFIX))
+        else {
+            continue;
+        };
+        if reference.target() == Some(commit) {
+            return Ok(Some(branch.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// 只保留最近一次推送的分支
+fn record_pushed_branch(repo: &Repository, remote_branch: &str, commit: Oid) -> Result<()> {
+    let mut stale = Vec::new();
+    for reference in repo.references()? {
+        if let Some(name) = reference?.name() {
+            if name.starts_with(PUSHED_REF_PREFIX) {
+                stale.push(name.to_string());
+            }
+        }
+    }
+    for name in stale {
+        repo.find_reference(&name)?.delete()?;
+    }
+    repo.reference(
+        &format!("{}{}", PUSHED_REF_PREFIX, remote_branch),
+        commit,
+        true,
+        "simple-docs-bot: pushed",
+    )?;
+    Ok(())
+}
+
+fn mark_pull_request_opened(config: &GitConfig, remote_branch: &str) -> Result<()> {
+    let repo = Repository::open(&config.repository_dir)?;
+    let mut pushed = repo.find_reference(&format!("{}{}", PUSHED_REF_PREFIX, remote_branch))?;
+    let commit = pushed
+        .target()
+        .ok_or(anyhow!("pushed ref is not a direct reference"))?;
+    repo.reference(
+        PULL_REQUEST_REF,
+        commit,
+        true,
+        "simple-docs-bot: pull request opened",
+    )?;
+    pushed.delete()?;
+    Ok(())
+}
+
+fn summarize(
+    repo: &Repository,
+    upstream: Option<&Commit>,
+    head: &Commit,
+    records_path: Option<&Path>,
+) -> Result<PushSummary> {
+    let old_tree = upstream.map(|commit| commit.tree()).transpose()?;
+    let diff = repo.diff_tree_to_tree(old_tree.as_ref(), Some(&head.tree()?), None)?;
+    let mut summary = PushSummary {
+        commit: head.id().to_string()[..7].to_string(),
+        branch: String::new(),
+        remote_branch: String::new(),
+        added: 0,
+        modified: 0,
+        deleted: 0,
+        records: None,
+    };
+    for delta in diff.deltas() {
+        match delta.status() {
+            Delta::Added => summary.added += 1,
+            Delta::Deleted => summary.deleted += 1,
+            _ => summary.modified += 1,
+        }
+    }
+    if let Some(head_records) = records_path
+        .map(|path| generated_records(repo, head, path))
+        .transpose()?
+        .flatten()
+    {
+        let upstream_records = match (upstream, records_path) {
+            (Some(upstream), Some(path)) => {
+                generated_records(repo, upstream, path)?.unwrap_or_default()
+            }
+            _ => HashSet::new(),
+        };
+        summary.records = Some(RecordChanges {
+            added: head_records.difference(&upstream_records).count(),
+            deleted: upstream_records.difference(&head_records).count(),
+        });
+    }
+    Ok(summary)
+}
+
+/// 提交中生成的记录 ID，文件不存在时为 None
+fn generated_records(
+    repo: &Repository,
+    commit: &Commit,
+    path: &Path,
+) -> Result<Option<HashSet<String>>> {
+    let entry = match commit.tree()?.get_path(path) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let blob = repo.find_blob(entry.id())?;
+    Ok(Some(serde_json::from_slice(blob.content())?))
+}
+
+fn current_branch(repo: &Repository) -> Result<String> {
+    let head = repo.head()?;
+    head.shorthand()
+        .map(str::to_string)
+        .ok_or(anyhow!("HEAD is not pointing to a branch"))
+}
+
+/// 拉取远程分支并将本地提交变基到其上，返回远程分支的最新提交，远程分支不存在时跳过
+fn sync_with_remote(repo: &Repository, config: &GitConfig) -> Result<Option<Oid>> {
+    let branch = current_branch(repo)?;
+    if let Ok(mut reference) = repo.find_reference(REMOTE_TRACKING_REF) {
+        reference.delete()?;
+    }
+    let mut remote = repo.remote_anonymous(&remote_url(&config.url))?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(remote_callbacks(&config.credential));
+    let refspec = format!("+refs/heads/{}:{}", branch, REMOTE_TRACKING_REF);
+    remote.fetch(&[refspec], Some(&mut options), None)?;
+    let upstream = match repo.find_reference(REMOTE_TRACKING_REF) {
+        Ok(reference) => repo.reference_to_annotated_commit(&reference)?,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let (analysis, _) = repo.merge_analysis(&[&upstream])?;
+    if analysis.is_fast_forward() {
+        repo.head()?
+            .set_target(upstream.id(), "simple-docs-bot: fast-forward")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+    } else if !analysis.is_up_to_date() {
+        rebase_onto(repo, config, &upstream)?;
+    }
+    Ok(Some(upstream.id()))
+}
+
+fn rebase_onto(repo: &Repository, config: &GitConfig, upstream: &AnnotatedCommit) -> Result<()> {
+    let signature = Signature::now(&config.username, &config.user_email)?;
+    let mut rebase = repo.rebase(None, Some(upstream), None, None)?;
+    while let Some(operation) = rebase.next() {
+        if let Err(e) = operation {
+            rebase.abort()?;
+            return Err(e.into());
+        }
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            let paths = conflict_paths(&index)?;
+            rebase.abort()?;
+            return Err(GitConflict { paths }.into());
+        }
+        match rebase.commit(None, &signature, None) {
+            Ok(_) => {}
+            Err(e) if e.code() == ErrorCode::Applied => {}
+            Err(e) => {
+                rebase.abort()?;
+                return Err(e.into());
+            }
+        }
+    }
+    rebase.finish(Some(&signature))?;
+    Ok(())
+}
+
+fn conflict_paths(index: &Index) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            paths.push(String::from_utf8_lossy(&entry.path).to_string());
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// 相当于 `git add -A && git commit`，工作区没有变化时不提交
+fn commit_all(repo: &Repository, config: &GitConfig, message: &str) -> Result<Option<Oid>> {
+    let mut index = repo.index()?;
+    index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"].iter(), None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => None,
+        Err(e) => return Err(e.into()),
+    };
+    if parent
+        .as_ref()
+        .is_some_and(|parent| parent.tree_id() == tree.id())
+    {
+        return Ok(None);
+    }
+    let signature = Signature::now(&config.username, &config.user_email)?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    Ok(Some(repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?))
+}
+
+fn push(repo: &Repository, config: &GitConfig, branch: &str, remote_branch: &str) -> Result<()> {
+    let refspec = format!("refs/heads/{}:refs/heads/{}", branch, remote_branch);
+    let mut remote = repo.remote_anonymous(&remote_url(&config.url))?;
+    let mut rejected = Vec::new();
+    let mut callbacks = remote_callbacks(&config.credential);
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(status) = status {
+            rejected.push(format!("{}: {}", refname, status));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    remote.push(&[refspec], Some(&mut options))?;
+    drop(options);
+    if !rejected.is_empty() {
+        let err_msg = format!("git push rejected: {}", rejected.join(", "));
+        error!("{}", &err_msg);
+        return Err(anyhow!(err_msg));
+    }
+    Ok(())
+}
+
+/// 凭据只通过 libgit2 回调传递，不会出现在远程地址、命令行或错误信息中
+fn remote_callbacks(credential: &GitCredential) -> RemoteCallbacks<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut attempted = false;
+    callbacks.credentials(move |_url, username_from_url, allowed| {
+        if allowed.contains(CredentialType::USERNAME) {
+            return match credential {
+                GitCredential::Token { username, .. } | GitCredential::SshKey { username, .. } => {
+                    Cred::username(username)
+                }
+                GitCredential::None => Cred::username(username_from_url.unwrap_or("git")),
+            };
+        }
+        // libgit2 在认证失败后会再次调用回调，只尝试一次避免死循环
+        if attempted {
+            return Err(git2::Error::from_str("git authentication failed"));
+        }
+        attempted = true;
+        match credential {
+            GitCredential::Token { username, token }
+                if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) =>
+            {
+                Cred::userpass_plaintext(username, token)
+            }
+            GitCredential::SshKey {
+                username,
+                private_key,
+                passphrase,
+            } if allowed.contains(CredentialType::SSH_KEY) => Cred::ssh_key(
+                username_from_url.unwrap_or(username),
+                None,
+                Path::new(private_key),
+                passphrase.as_deref(),
+            ),
+            _ if allowed.contains(CredentialType::DEFAULT) => Cred::default(),
+            _ => Err(git2::Error::from_str(
+                "no matching git credential configured",
+            )),
+        }
+    });
+    callbacks
+}
+
+/// 兼容旧配置中省略协议的地址（如 `example.com:443/example/example`）
+fn remote_url(url: &str) -> String {
+    if url.contains("://") || url.contains('@') || Path::new(url).exists() {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    }
+}
+
 pub fn git_init(config: &GitConfig) -> Result<()> {
-    if config.repository_dir.as_str() == "dev" { 
+    if config.repository_dir.as_str() == "dev" {
         return Ok(());
     }
-    let dir = format!("/docs/{}", config.repository_dir.clone());
+    if config.disable_owner_validation {
+        // 全局设置，在打开任何仓库之前调用，之后不会再有其他线程同时修改
+        unsafe {
+            git2::opts::set_verify_owner_validation(false)?;
+        }
+    } else if let Err(e) = trust_repository_dir(&config.repository_dir) {
+        warn!(
+            "trust git repository {} error: {}",
+            config.repository_dir, e
+        );
+    }
+    match Repository::open(&config.repository_dir) {
+        Ok(repo) => info!("git repository: {:?}", repo.path()),
+        Err(e) if e.code() == ErrorCode::Owner => error!(
+            "open git repository {} error: {}, set git.disable_owner_validation to skip the owner check",
+            config.repository_dir, e
+        ),
+        Err(e) => error!("open git repository {} error: {}", config.repository_dir, e),
+    }
+    Ok(())
+}
+
+/// 与之前通过 git 命令设置 `safe.directory` 相同，信任配置的仓库目录：
+/// 容器中挂载的仓库属主常与运行用户不同
+fn trust_repository_dir(repository_dir: &str) -> Result<()> {
+    let global_config = match Config::find_global() {
+        Ok(path) => path,
+        Err(_) => PathBuf::from(std::env::var_os("HOME").ok_or(anyhow!("HOME is not set"))?)
+            .join(".gitconfig"),
+    };
+    let dir = fs::canonicalize(repository_dir)?;
+    if add_safe_directory(&global_config, &dir)? {
+        info!("add git safe.directory: {:?}", dir);
+    }
+    Ok(())
+}
+
+/// 已存在时不重复添加，返回是否添加
+fn add_safe_directory(config_path: &Path, dir: &Path) -> Result<bool> {
+    let mut config = Config::open(config_path)?;
+    let dir = dir.to_string_lossy().to_string();
+    let mut trusted = false;
+    let mut entries = config.multivar("safe.directory", None)?;
+    while let Some(entry) = entries.next() {
+        trusted |= entry?.value() == Some(dir.as_str());
+    }
+    drop(entries);
+    if trusted {
+        return Ok(false);
+    }
+    // 只替换与该目录相同的值，已确认不存在，因此总是追加一条
+    let mut exact = String::new();
+    for c in dir.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            exact.push('\\');
+        }
+        exact.push(c);
+    }
+    config.set_multivar("safe.directory", &format!("^{}$", exact), &dir)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ForgeConfig, GenerateConfig};
+    use crate::test_utils::{MockHttpServer, TestBot, ADMIN_ID};
+    use std::fs;
 
-    let output = Command::new("git")
-        .arg("config")
-        .arg("--global")
-        .arg("--add")
-        .arg("safe.directory")
-        .arg(dir)
-        .output()?;
-    if !output.status.success() {
-        let err_msg = format!(
-            "set git safe.directory error: {}",
-            String::from_utf8_lossy(&output.stderr)
+    fn reply_text(reply: Option<Vec<ApiPayload>>) -> String {
+        match &reply.unwrap()[..] {
+            [ApiPayload::SendPrivateMsg(SendPrivateMsg { message, .. })] => match &message[..] {
+                [MessageSegment::Text { data }] => data.text.clone(),
+                other => panic!("unexpected message: {:?}", other),
+            },
+            other => panic!("unexpected payload: {:?}", other),
+        }
+    }
+
+    fn git_config(repository_dir: &Path, url: &Path) -> GitConfig {
+        GitConfig {
+            repository_dir: repository_dir.to_string_lossy().to_string(),
+            url: url.to_string_lossy().to_string(),
+            username: "bot".to_string(),
+            user_email: "bot@example.com".to_string(),
+            credential: GitCredential::None,
+            publish: PublishMode::Push,
+            disable_owner_validation: false,
+        }
+    }
+
+    #[test]
+    fn commit_and_push_to_local_remote() {
+        let remote_dir = tempfile::tempdir().unwrap();
+        let remote = Repository::init_bare(remote_dir.path()).unwrap();
+        let work_dir = tempfile::tempdir().unwrap();
+        let work = Repository::init(work_dir.path()).unwrap();
+        let config = git_config(work_dir.path(), remote_dir.path());
+
+        fs::create_dir_all(work_dir.path().join("share")).unwrap();
+        fs::write(work_dir.path().join("share/README.md"), "分享").unwrap();
+        let summary = commit_and_push(&config, None).unwrap().unwrap();
+        assert_eq!(
+            (summary.added, summary.modified, summary.records),
+            (1, 0, None)
         );
-        error!("{}", &err_msg);
+        assert!(commit_all(&work, &config, "nothing").unwrap().is_none());
+        assert_eq!(commit_and_push(&config, None).unwrap(), None);
+
+        fs::write(work_dir.path().join("share/README.md"), "分享\n").unwrap();
+        fs::write(work_dir.path().join("share/2026-10.md"), "十月").unwrap();
+        let summary = commit_and_push(&config, None).unwrap().unwrap();
+        assert_eq!(
+            (summary.added, summary.modified, summary.deleted),
+            (1, 1, 0)
+        );
+
+        let branch = work.head().unwrap().shorthand().unwrap().to_string();
+        let pushed = remote
+            .find_reference(&format!("refs/heads/{}", branch))
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(pushed.author().name(), Some("bot"));
+        assert!(pushed
+            .tree()
+            .unwrap()
+            .get_path(Path::new("share/2026-10.md"))
+            .is_ok());
+    }
+
+    #[test]
+    fn debug_output_hides_secrets() {
+        let mut config = git_config(Path::new("."), Path::new("."));
+        config.credential = GitCredential::Token {
+            username: "bot".to_string(),
+            token: "secret-token".to_string(),
+        };
+        assert!(!format!("{:?}", config).contains("secret-token"));
+        config.credential = GitCredential::SshKey {
+            username: "git".to_string(),
+            private_key: "/keys/id_ed25519".to_string(),
+            passphrase: Some("secret-passphrase".to_string()),
+        };
+        assert!(!format!("{:?}", config).contains("secret-passphrase"));
+    }
+
+    /// 远程仓库和两个克隆，模拟有人在网页上直接修改了文档仓库
+    fn remote_with_two_clones() -> (
+        tempfile::TempDir,
+        GitConfig,
+        GitConfig,
+        Vec<tempfile::TempDir>,
+    ) {
+        let remote_dir = tempfile::tempdir().unwrap();
+        Repository::init_bare(remote_dir.path()).unwrap();
+        let bot_dir = tempfile::tempdir().unwrap();
+        Repository::init(bot_dir.path()).unwrap();
+        let bot = git_config(bot_dir.path(), remote_dir.path());
+        fs::write(bot_dir.path().join("README.md"), "首页\n").unwrap();
+        fs::write(bot_dir.path().join("notes.md"), "原始内容\n").unwrap();
+        commit_and_push(&bot, None).unwrap();
+
+        let human_dir = tempfile::tempdir().unwrap();
+        Repository::clone(&remote_dir.path().to_string_lossy(), human_dir.path()).unwrap();
+        let human = git_config(human_dir.path(), remote_dir.path());
+        (remote_dir, bot, human, vec![bot_dir, human_dir])
+    }
+
+    fn write_file(config: &GitConfig, path: &str, content: &str) {
+        fs::write(Path::new(&config.repository_dir).join(path), content).unwrap();
+    }
+
+    #[test]
+    fn rebases_onto_remote_changes_before_push() {
+        let (remote_dir, bot, human, _dirs) = remote_with_two_clones();
+        write_file(&human, "notes.md", "手动修改\n");
+        commit_and_push(&human, None).unwrap();
+        write_file(&bot, "README.md", "首页\n- 2026-10\n");
+        commit_and_push(&bot, None).unwrap();
+
+        let remote = Repository::open_bare(remote_dir.path()).unwrap();
+        let branch = current_branch(&Repository::open(&bot.repository_dir).unwrap()).unwrap();
+        let tree = remote
+            .find_reference(&format!("refs/heads/{}", branch))
+            .unwrap()
+            .peel_to_tree()
+            .unwrap();
+        let read = |path: &str| {
+            let blob = tree
+                .get_path(Path::new(path))
+                .unwrap()
+                .to_object(&remote)
+                .unwrap();
+            String::from_utf8(blob.as_blob().unwrap().content().to_vec()).unwrap()
+        };
+        assert_eq!(read("notes.md"), "手动修改\n");
+        assert_eq!(read("README.md"), "首页\n- 2026-10\n");
     }
-    
-    let output = Command::new("git")
-        .arg("config")
-        .arg("--global")
-        .arg("user.name")
-        .arg(config.username.clone())
-        .output()?;
-    if !output.status.success() {
-        let err_msg = format!(
-            "set git user.name error: {}",
-            String::from_utf8_lossy(&output.stderr)
+
+    #[test]
+    fn safe_directory_is_added_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("gitconfig");
+        let repository_dir = dir.path().join("docs (1).repo");
+        Config::open(&config_path)
+            .unwrap()
+            .set_multivar("safe.directory", "^$", "/other")
+            .unwrap();
+
+        assert!(add_safe_directory(&config_path, &repository_dir).unwrap());
+        assert!(!add_safe_directory(&config_path, &repository_dir).unwrap());
+        let config = Config::open(&config_path).unwrap();
+        let mut entries = config.multivar("safe.directory", None).unwrap();
+        let mut values = Vec::new();
+        while let Some(entry) = entries.next() {
+            values.push(entry.unwrap().value().unwrap().to_string());
+        }
+        assert_eq!(
+            values,
+            vec![
+                "/other".to_string(),
+                repository_dir.to_string_lossy().to_string()
+            ]
         );
-        error!("{}", &err_msg);
     }
 
-    let output = Command::new("git")
-        .arg("config")
-        .arg("--global")
-        .arg("user.email")
-        .arg(config.user_email.clone())
-        .output()
-        .expect("Failed to set git user.email");
-    if !output.status.success() {
-        let err_msg = format!(
-            "set git user.email error: {}",
-            String::from_utf8_lossy(&output.stderr)
+    #[tokio::test]
+    async fn conflicting_remote_changes_are_reported() {
+        let (_remote_dir, bot, human, _dirs) = remote_with_two_clones();
+        write_file(&human, "notes.md", "手动修改\n");
+        commit_and_push(&human, None).unwrap();
+        write_file(&bot, "notes.md", "机器人修改\n");
+
+        let e = commit_and_push(&bot, None).unwrap_err();
+        let conflict = e.downcast_ref::<GitConflict>().unwrap();
+        assert_eq!(conflict.paths, vec!["notes.md".to_string()]);
+        let repo = Repository::open(&bot.repository_dir).unwrap();
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+        assert_eq!(
+            fs::read_to_string(Path::new(&bot.repository_dir).join("notes.md")).unwrap(),
+            "机器人修改\n"
         );
-        error!("{}", &err_msg);
+
+        let test_bot = TestBot::new().await;
+        let reply = auto_git_task(&bot, ADMIN_ID, &test_bot.bot_help)
+            .await
+            .unwrap()
+            .unwrap();
+        let ApiPayload::SendPrivateMsg(SendPrivateMsg {
+            user_id, message, ..
+        }) = &reply[0]
+        else {
+            panic!("unexpected payload: {:?}", reply);
+        };
+        assert_eq!(*user_id, ADMIN_ID);
+        let MessageSegment::Text { data } = &message[0] else {
+            panic!("unexpected message: {:?}", message);
+        };
+        assert!(data.text.ends_with("请手动处理后重试：\nnotes.md"));
+    }
+
+    #[tokio::test]
+    async fn git_task_reports_summary_or_no_changes() {
+        let test_bot = TestBot::new().await;
+        let uuid = test_bot
+            .bot_help
+            .insert_new_record(0, "第一条".to_string())
+            .await
+            .unwrap();
+        test_bot
+            .bot_help
+            .insert_new_record(0, "第二条".to_string())
+            .await
+            .unwrap();
+        let remote_dir = tempfile::tempdir().unwrap();
+        Repository::init_bare(remote_dir.path()).unwrap();
+        let work = Repository::init(test_bot.share_path()).unwrap();
+        let config = git_config(&test_bot.share_path(), remote_dir.path());
+        let generate = |records| {
+            let bot_help = test_bot.bot_help.clone();
+            async move {
+                generator::generate_by_records(&GenerateConfig::default(), &bot_help, records)
+                    .await
+                    .unwrap()
+            }
+        };
+        let records = test_bot.bot_help.select_all_records().await.unwrap();
+        generate(records).await;
+
+        let reply = reply_text(
+            auto_git_task(&config, ADMIN_ID, &test_bot.bot_help)
+                .await
+                .unwrap(),
+        );
+        let head = work
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .id()
+            .to_string();
+        assert!(reply.starts_with(&format!("git任务完成\n提交：{}\n", &head[..7])));
+        assert!(reply.ends_with("\n新增记录：2，删除记录：0"));
+        let reply = reply_text(
+            auto_git_task(&config, ADMIN_ID, &test_bot.bot_help)
+                .await
+                .unwrap(),
+        );
+        assert_eq!(reply, "git任务完成，没有需要提交的修改");
+
+        test_bot.bot_help.delete_record(&uuid).await.unwrap();
+        let records = test_bot.bot_help.select_all_records().await.unwrap();
+        generate(records).await;
+        let reply = reply_text(
+            auto_git_task(&config, ADMIN_ID, &test_bot.bot_help)
+                .await
+                .unwrap(),
+        );
+        assert!(reply.ends_with("\n新增记录：0，删除记录：1"));
+    }
+
+    #[tokio::test]
+    async fn pull_request_mode_pushes_a_dated_branch() {
+        let test_bot = TestBot::new().await;
+        let remote_dir = tempfile::tempdir().unwrap();
+        let remote = Repository::init_bare(remote_dir.path()).unwrap();
+        let work_dir = tempfile::tempdir().unwrap();
+        let work = Repository::init(work_dir.path()).unwrap();
+        let mut config = git_config(work_dir.path(), remote_dir.path());
+        fs::write(work_dir.path().join("README.md"), "分享").unwrap();
+        commit_and_push(&config, None).unwrap();
+        let branch = current_branch(&work).unwrap();
+        let protected = remote
+            .refname_to_id(&format!("refs/heads/{}", branch))
+            .unwrap();
+
+        let http = MockHttpServer::start().await;
+        http.route(
+            "POST",
+            "/repos/owner/docs/pulls",
+            201,
+            "application/json",
+            br#"{"html_url": "https://github.com/owner/docs/pull/7"}"#,
+        );
+        config.publish = PublishMode::PullRequest(ForgeConfig {
+            api_url: http.url(""),
+            repository: "owner/docs".to_string(),
+            token: "secret".to_string(),
+            ..ForgeConfig::default()
+        });
+        fs::write(work_dir.path().join("README.md"), "分享\n- 2026-10").unwrap();
+        let reply = reply_text(
+            auto_git_task(&config, ADMIN_ID, &test_bot.bot_help)
+                .await
+                .unwrap(),
+        );
+        assert!(reply.ends_with("\n合并请求：https://github.com/owner/docs/pull/7"));
+
+        assert_eq!(
+            remote
+                .refname_to_id(&format!("refs/heads/{}", branch))
+                .unwrap(),
+            protected
+        );
+        let request: serde_json::Value = serde_json::from_slice(&http.requests()[0].body).unwrap();
+        let head = request["head"].as_str().unwrap();
+        assert!(head.starts_with("bot/"));
+        assert_eq!(request["base"], branch.as_str());
+        assert_eq!(
+            remote
+                .refname_to_id(&format!("refs/heads/{}", head))
+                .unwrap(),
+            work.head().unwrap().target().unwrap()
+        );
+
+        let reply = reply_text(
+            auto_git_task(&config, ADMIN_ID, &test_bot.bot_help)
+                .await
+                .unwrap(),
+        );
+        assert_eq!(reply, "git任务完成，没有需要提交的修改");
+        assert_eq!(http.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_pull_request_is_retried_on_the_pushed_branch() {
+        let test_bot = TestBot::new().await;
+        let remote_dir = tempfile::tempdir().unwrap();
+        Repository::init_bare(remote_dir.path()).unwrap();
+        let work_dir = tempfile::tempdir().unwrap();
+        Repository::init(work_dir.path()).unwrap();
+        let mut config = git_config(work_dir.path(), remote_dir.path());
+        fs::write(work_dir.path().join("README.md"), "分享").unwrap();
+        commit_and_push(&config, None).unwrap();
+
+        let http = MockHttpServer::start().await;
+        http.route(
+            "POST",
+            "/repos/owner/docs/pulls",
+            500,
+            "application/json",
+            br#"{"message": "Server Error"}"#,
+        );
+        config.publish = PublishMode::PullRequest(ForgeConfig {
+            api_url: http.url(""),
+            repository: "owner/docs".to_string(),
+            token: "secret".to_string(),
+            ..ForgeConfig::default()
+        });
+        fs::write(work_dir.path().join("README.md"), "分享\n- 2026-10").unwrap();
+        assert!(auto_git_task(&config, ADMIN_ID, &test_bot.bot_help)
+            .await
+            .is_err());
+
+        http.route(
+            "POST",
+            "/repos/owner/docs/pulls",
+            201,
+            "application/json",
+            br#"{"html_url": "https://github.com/owner/docs/pull/8"}"#,
+        );
+        let reply = reply_text(
+            auto_git_task(&config, ADMIN_ID, &test_bot.bot_help)
+                .await
+                .unwrap(),
+        );
+        assert!(reply.ends_with("\n合并请求：https://github.com/owner/docs/pull/8"));
+        let heads: Vec<String> = http
+            .requests()
+            .iter()
+            .map(|request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                body["head"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(heads.len(), 2);
+        assert_eq!(heads[0], heads[1]);
+
+        let reply = reply_text(
+            auto_git_task(&config, ADMIN_ID, &test_bot.bot_help)
+                .await
+                .unwrap(),
+        );
+        assert_eq!(reply, "git任务完成，没有需要提交的修改");
+        assert_eq!(http.requests().len(), 2);
     }
-    info!("{}", "auto set git config");
-    Ok(())
 }
@@ -1,9 +1,12 @@
-use std::fs::{create_dir_all, File};
+use std::collections::HashMap;
+use std::fs::{create_dir_all, read_dir, rename, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::{anyhow, Result};
 use reqwest::get;
-use tracing::error;
+use tracing::{error, info, warn};
+use crate::bot_help::BotHelp;
+use crate::storage::Record;
 
 pub async fn get_image(url: String, image_type: String, save_path: String) -> Result<String> {
     let save_path = Path::new(&save_path);
@@ -23,4 +26,93 @@ pub async fn get_image(url: String, image_type: String, save_path: String) -> Re
         error!("Download Image Error, Status Code: {}", response.status());
         Err(anyhow!("Download Image Error"))
     }
-} 
\ No newline at end of file
+}
+
+/// 记录的图片目录，相对于分享目录，按记录创建的月份存放
+pub fn record_image_dir(record: &Record) -> String {
+    format!("{}/{}", record.created_at.format("%Y-%m"), record.id)
+}
+
+/// 图片相对于分享目录的路径；旧数据只保存了文件名，按记录创建月份推断
+pub fn image_path(record: &Record, content: &str) -> PathBuf {
+    if content.contains('/') {
+        PathBuf::from(content)
+    } else {
+        Path::new(&record_image_dir(record)).join(content)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RepairReport {
+    pub moved: usize,
+    pub missing: usize,
+}
+
+/// 将已保存的图片移动到记录创建月份的目录下，并把内容中的图片改为相对于分享目录的路径
+pub async fn repair_image_paths(bot_help: &BotHelp) -> Result<RepairReport> {
+    let share_path = PathBuf::from(bot_help.share_path().await?);
+    let records: HashMap<String, Record> = bot_help
+        .select_all_records()
+        .await?
+        .into_iter()
+        .map(|record| (record.id.clone(), record))
+        .collect();
+    let mut report = RepairReport::default();
+    for content in bot_help.select_all_content().await? {
+        if content.content_type != "image" {
+            continue;
+        }
+        let Some(record) = records.get(&content.uuid) else {
+            continue;
+        };
+        let file_name = content.content.rsplit('/').next().unwrap_or_default();
+        let target = format!("{}/{}", record_image_dir(record), file_name);
+        let target_path = share_path.join(&target);
+        if content.content == target && target_path.exists() {
+            continue;
+        }
+        if !target_path.exists() {
+            match find_image(&share_path, &content.content, &content.uuid, file_name)? {
+                Some(source) => {
+                    if let Some(parent) = target_path.parent() {
+                        create_dir_all(parent)?;
+                    }
+                    info!("Move Image {:?} To {:?}", source, target_path);
+                    rename(source, &target_path)?;
+                }
+                None => {
+                    warn!("Image Not Found: {} {}", content.uuid, content.content);
+                    report.missing += 1;
+                    continue;
+                }
+            }
+        }
+        bot_help
+            .update_content(&content.uuid, &content.content, target)
+            .await?;
+        report.moved += 1;
+    }
+    Ok(report)
+}
+
+/// 先按内容中保存的路径查找，再在各月份目录下查找 `{uuid}/{file_name}`
+fn find_image(
+    share_path: &Path,
+    content: &str,
+    uuid: &str,
+    file_name: &str,
+) -> Result<Option<PathBuf>> {
+    if content.contains('/') && share_path.join(content).is_file() {
+        return Ok(Some(share_path.join(content)));
+    }
+    if !share_path.exists() {
+        return Ok(None);
+    }
+    for entry in read_dir(share_path)? {
+        let candidate = entry?.path().join(uuid).join(file_name);
+        if candidate.is_file() {
+            return Ok(Some(candidate));
+        }
+    }
+    Ok(None)
+}
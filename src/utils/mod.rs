@@ -1,4 +1,7 @@
+pub mod attachment;
+pub mod forge;
+pub mod forward_message;
 pub mod git;
-pub mod json_parse;
+pub mod card;
 pub mod reply_message;
 pub mod image;